@@ -0,0 +1,9829 @@
+//! A halo2 chip for bitwise AND over `WORD_BITS`-bit words, with the circuits,
+//! prover and witness files built on it. `prelude` has the parts a caller
+//! needs.
+
+use halo2_gadgets::poseidon::{
+    primitives::{self as poseidon, ConstantLength, P128Pow5T3},
+    Hash as PoseidonHash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Chip, Layouter, Region, SimpleFloorPlanner},
+    dev::CircuitCost,
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Any, Assigned, Assignment,
+        Circuit, Column, ConstraintSystem, Error, Expression, Fixed, FloorPlanner, Instance,
+        ProvingKey, Selector, SingleVerifier, TableColumn, VerifyingKey,
+    },
+    poly::{
+        commitment::{Blind, Params},
+        EvaluationDomain, Rotation,
+    },
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, Transcript},
+};
+use pasta_curves::{
+    group::{
+        ff::{Field, PrimeField},
+        Curve,
+    },
+    EqAffine, Fp,
+};
+use rand_core::RngCore;
+use std::{
+    cell::Cell,
+    collections::{BTreeSet, HashMap},
+    fmt,
+    marker::PhantomData,
+};
+
+#[cfg(test)]
+use halo2_proofs::dev::MockProver;
+#[cfg(test)]
+use rand_core::OsRng;
+
+/// The chip, its instruction trait and the circuits and prover built on it,
+/// along with `Fp`, the field all of them are instantiated over.
+///
+/// ```
+/// use and_circuit::prelude::*;
+/// use halo2_proofs::dev::MockProver;
+///
+/// let circuit = MyCircuit::<Fp, 8> {
+///     a: Some(Fp::from(3)),
+///     b: Some(Fp::from(6)),
+///     output_row: 0,
+/// };
+/// let public_inputs = vec![vec![Fp::from(3 & 6)]];
+/// let prover = MockProver::run(circuit.required_k(), &circuit, public_inputs).unwrap();
+/// assert_eq!(prover.verify(), Ok(()));
+/// ```
+pub mod prelude {
+    pub use crate::{
+        keygen, load_witness, min_k, min_k_for_vec, prove, save_witness, verify, verify_checked,
+        AndAccumulator, AndChip, AndConfig, AndError, CircuitInputs, LookupAndCircuit,
+        MultiAndCircuit, MyCircuit, NumericInstructions, Proof, Prover, SplitTableCircuit,
+        SpreadTable, TableBackend, Word,
+    };
+    pub use pasta_curves::Fp;
+}
+
+pub trait NumericInstructions<F: FieldExt>: Chip<F> {
+    /// Variable representing a number.
+    type Word;
+
+    /// Loads a number into the circuit as a private input.
+    fn load_private(&self, layouter: impl Layouter<F>, a: Option<F>) -> Result<Self::Word, Error>;
+
+    /// Loads the public input at `row` of the `column`th instance column, the
+    /// reverse of `expose_public`. The word is copy-constrained to the
+    /// instance cell, and not range-checked. A `column` past the last one is
+    /// `Error::Synthesis`.
+    fn load_public(
+        &self,
+        layouter: impl Layouter<F>,
+        column: usize,
+        row: usize,
+    ) -> Result<Self::Word, Error>;
+
+    fn add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    fn verify_decompose(
+        &self,
+        layouter: impl Layouter<F>,
+        c: Self::Word,
+    ) -> Result<(Self::Word, Self::Word), Error>;
+
+    fn compose(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns the bitwise AND of two words.
+    fn and(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Starts an AND reduction from `seed`, decomposing it into the spread
+    /// lanes `and_accumulate` works on. This range-checks the seed, at two
+    /// rows.
+    fn and_seed(
+        &self,
+        layouter: impl Layouter<F>,
+        seed: Self::Word,
+    ) -> Result<AndAccumulator<Self::Word>, Error>;
+
+    /// Returns `acc & word`, one step of an AND reduction over a stream of
+    /// words: `and_finish(fold(and_seed(seed), and_accumulate))`.
+    ///
+    /// The accumulator stays in spread form between steps, the way
+    /// `column_and` keeps it: the AND lanes of one step are added to the
+    /// next word's lanes directly, and nothing is composed until
+    /// `and_finish`. A step costs 10 rows, against an `and`'s 14.
+    ///
+    /// Seeding with `2^WORD_BITS - 1`, the identity of AND, reduces a whole
+    /// stream. To split the stream over several proofs, finish the
+    /// accumulator at the end of each chunk, expose it, and load it as the
+    /// seed of the next one, with the verifier checking that the two public
+    /// inputs match.
+    fn and_accumulate(
+        &self,
+        layouter: impl Layouter<F>,
+        acc: AndAccumulator<Self::Word>,
+        word: Self::Word,
+    ) -> Result<AndAccumulator<Self::Word>, Error>;
+
+    /// Composes an accumulator's lanes into the word it stands for.
+    fn and_finish(
+        &self,
+        layouter: impl Layouter<F>,
+        acc: AndAccumulator<Self::Word>,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns the AND of all of `words`, folded left to right with `and`:
+    /// `words.len() - 1` ANDs. A single word is returned as it is, after
+    /// range-checking it the way an AND would. Panics on an empty slice.
+    fn and_all(
+        &self,
+        layouter: impl Layouter<F>,
+        words: &[Self::Word],
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns the column-wise AND of a boolean matrix given as rows of
+    /// words: bit `j` of the result is the AND of bit `j` of every row. A
+    /// single row is returned as it is, range-checked. Panics on an empty
+    /// slice.
+    ///
+    /// The result is the same as `and_all`'s, but the fold stays in spread
+    /// form, through `and_seed`, `and_accumulate` and `and_finish`. Every
+    /// `and` ends by composing its AND lanes into a word, which the next
+    /// step then has to decompose again. Here the AND lanes of one step are
+    /// added to the next row's lanes directly, and only the final pair is
+    /// composed, so each row after the first costs a decomposition,
+    /// two adds and two decompositions of the sums, 10 rows to an `and`'s 14.
+    /// `n` rows take `10 * (n - 1) + 4` rows against `14 * (n - 1)`. With the
+    /// `DirectLookup` backend the lanes go through its lookups instead, at 6
+    /// rows a row.
+    fn column_and(
+        &self,
+        layouter: impl Layouter<F>,
+        rows: &[Self::Word],
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns the bitwise XOR of two words.
+    ///
+    /// The 2-bit lanes of `ae + be` hold `a_i + b_i`: the low bit is the XOR
+    /// and the high bit the AND. So XOR composes the low halves where `and`
+    /// composes the high ones, and costs the same `AND_ROWS`, with no gate
+    /// of its own. It can't skip a decomposition: the sum has to be split
+    /// for its low halves to be constrained at all.
+    fn xor(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns the XOR of all of `words`, a simple checksum. An empty slice
+    /// gives 0, the XOR identity, loaded from the fixed column; a single
+    /// word is returned as it is, range-checked.
+    ///
+    /// Like `column_and`, the fold stays in spread form: the XOR lanes of one
+    /// step are added to the next word's lanes directly, rather than composed
+    /// by `xor` and decomposed again. `n` words take `10 * (n - 1) + 4` rows,
+    /// linear in `n`, against `14 * (n - 1)` for chained `xor`s.
+    fn xor_fold(
+        &self,
+        layouter: impl Layouter<F>,
+        words: &[Self::Word],
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns `(2^WORD_BITS - 1) - a`, the bitwise complement of `a`.
+    ///
+    /// The `add` gate is laid out as `out + a = mask`, with the mask fixed
+    /// through the constants column. Range-checking `out` then range-checks
+    /// `a` as well, since `mask - a` only lands in range for an `a` that is.
+    fn not(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error>;
+
+    /// Splits a word into its bits, least significant first. Every bit is
+    /// constrained to be boolean, so this also range-checks the word.
+    fn decompose_bits(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+    ) -> Result<Vec<Self::Word>, Error>;
+
+    /// Recombines boolean words, least significant first, into a single word
+    /// with weights `2^i`, e.g. a byte from eight bits of per-bit logic.
+    ///
+    /// Every bit is constrained to be boolean by the same `bits` gate that
+    /// `decompose_bits` uses, so given all `WORD_BITS` bits this is its exact
+    /// inverse. Fewer bits give a correspondingly narrower word.
+    fn compose_bits(
+        &self,
+        layouter: impl Layouter<F>,
+        bits: &[Self::Word],
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns the bit field `(a >> lo) & ((1 << (hi - lo)) - 1)`, the kind of
+    /// extraction struct-field parsing circuits are built from.
+    ///
+    /// The field is recomposed from bits `lo..hi` of the decomposition of
+    /// `a`, so it is range-checked to `hi - lo` bits. `lo == hi` gives zero.
+    /// Panics unless `lo <= hi <= WORD_BITS`.
+    fn bits(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        lo: u32,
+        hi: u32,
+    ) -> Result<Self::Word, Error>;
+
+    /// Shifts a word right by `n` bits. Shifting by `WORD_BITS` or more gives
+    /// zero.
+    fn shr(&self, layouter: impl Layouter<F>, a: Self::Word, n: u32) -> Result<Self::Word, Error>;
+
+    /// Shifts a word left by an in-circuit amount, dropping the bits shifted
+    /// out of the top: `a << shift` modulo `2^WORD_BITS`. `shift` is
+    /// constrained to be below `WORD_BITS`, and `a` is range-checked.
+    ///
+    /// This is a barrel shifter over the bits of `a`. With `L =
+    /// ceil(log2(WORD_BITS))`, bit `i` of `shift` drives stage `i`, which
+    /// selects between every bit as it is and the bit `2^i` places below it,
+    /// or 0 past the bottom. The `L` stages compose any shift up to `2^L - 1`
+    /// out of the bits of its amount, where a select per amount would need
+    /// `WORD_BITS` candidate shifts. The stages are `WORD_BITS` selects each,
+    /// at two rows apiece, for `2 * L * WORD_BITS` rows: 48 at 8 bits. On
+    /// top come the decompositions of `a` and `shift` and the final compose,
+    /// `WORD_BITS + 1` rows each, and the range check on `shift`, about 10
+    /// rows more.
+    fn shl_var(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        shift: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns `a mod 2^bits`, the low `bits` bits of `a`, range-checked to
+    /// `bits` bits. `bits == 0` gives zero, and `bits >= WORD_BITS` gives `a`
+    /// back, range-checked to `WORD_BITS`.
+    ///
+    /// This is `bits(a, 0, bits)`, and what an AND with the constant mask
+    /// `2^bits - 1` would give, named for the reduction it is. The input is
+    /// still a `WORD_BITS`-bit word; wider values have to be split first.
+    fn mod_pow2(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        bits: u32,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns the Gray code `a ^ (a >> 1)` of a word.
+    fn to_gray(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error>;
+
+    /// Inverts `to_gray`: bit `i` of the result is the XOR of bits `i` and up
+    /// of `g`.
+    ///
+    /// The prefix XOR is built by doubling, `g ^= g >> s` for `s = 1, 2, 4,
+    /// ...`, so it takes `ceil(log2(WORD_BITS))` rounds of `shr` and `xor`
+    /// and its row cost keeps growing with `WORD_BITS`, unlike `to_gray`'s
+    /// single round.
+    #[allow(clippy::wrong_self_convention)]
+    fn from_gray(&self, layouter: impl Layouter<F>, g: Self::Word) -> Result<Self::Word, Error>;
+
+    /// Widens a `from_bits`-bit word to `WORD_BITS` bits by padding it with
+    /// zeros, which leaves its value alone.
+    ///
+    /// The word is decomposed into bits and the low `from_bits` of them
+    /// recomposed and constrained equal to it, which range-checks it to
+    /// `from_bits` bits. Panics unless `from_bits <= WORD_BITS`.
+    fn zero_extend(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        from_bits: u32,
+    ) -> Result<Self::Word, Error>;
+
+    /// Widens a `from_bits`-bit two's-complement word to `WORD_BITS` bits by
+    /// copying its top bit into the new high bits.
+    ///
+    /// This range-checks the word to `from_bits` bits the same way as
+    /// `zero_extend`, and then composes the extended bits once more. Panics
+    /// unless `1 <= from_bits <= WORD_BITS`.
+    fn sign_extend(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        from_bits: u32,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns `a` with bit `index` set to `value`.
+    ///
+    /// `index` is a word, not a constant, so it is decoded into one-hot
+    /// flags with an `eq_const` against every bit position, and the flags are
+    /// constrained to sum to 1, which range-checks it below `WORD_BITS`.
+    /// `value` is asserted boolean, and each bit of `a` then goes through a
+    /// select on its flag before the bits are recomposed. All of that is
+    /// linear in `WORD_BITS`.
+    fn set_bit(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        index: Self::Word,
+        value: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Interleaves the bits of two words into the `2 * WORD_BITS`-bit Morton
+    /// code, with the bits of `x` at the even positions and those of `y` at
+    /// the odd ones.
+    ///
+    /// That is the spread form the even and odd lanes of `decompose` are
+    /// kept in: the code is `compose(spread(x), spread(y))`, and splitting it
+    /// back up with `verify_decompose` gives `(spread(x), spread(y))`. It is
+    /// built with `compose_bits` over the interleaved bits of both words,
+    /// which range-checks each of them to `WORD_BITS` bits.
+    fn morton(
+        &self,
+        layouter: impl Layouter<F>,
+        x: Self::Word,
+        y: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Rotates a word left by `n` bits.
+    fn rotl(&self, layouter: impl Layouter<F>, a: Self::Word, n: u32) -> Result<Self::Word, Error>;
+
+    /// Returns `a & rotl(a, k)`, a common diffusion step in hash functions.
+    ///
+    /// When `k` is a multiple of `WORD_BITS` this is `a & a = a`, and all
+    /// that is left of the AND is its range check on `a`. Otherwise it costs
+    /// the same as `rotl` followed by `and`: the rotation needs `a` in bits
+    /// and the AND needs it, and the rotated word, in spread lanes, and
+    /// reading the lanes off the bits takes more rows than the two-row
+    /// `verify_decompose` the AND does anyway. The decompositions are as
+    /// shared as they get.
+    fn and_rotated(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        k: u32,
+    ) -> Result<Self::Word, Error>;
+
+    /// Computes `a ^ rotl(b, 1) ^ c`, one lane of a Keccak-style theta step.
+    fn theta_lane(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+        c: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns the bitwise majority `(a & b) | (b & c) | (a & c)` of three
+    /// words, the `Maj` function of SHA-256.
+    ///
+    /// The spread lanes of all three words are summed at once, so every
+    /// 2-bit lane holds `a_i + b_i + c_i` and its high bit is the majority.
+    /// That is six decompositions, four adds and a compose, 26 rows in all.
+    fn majority(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+        c: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns the XOR of all `WORD_BITS` bits of a word, as a boolean word.
+    ///
+    /// This is a fold of `xor` over the bit decomposition, so it takes
+    /// `WORD_BITS - 1` XORs on top of the decomposition and its row cost grows
+    /// linearly with `WORD_BITS`.
+    fn parity(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error>;
+
+    /// Returns the number of set bits in a word.
+    ///
+    /// The bits of `decompose_bits` are summed with `add`, `WORD_BITS - 1` of
+    /// them at two rows each on top of the decomposition's `WORD_BITS + 1`.
+    fn popcount(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error>;
+
+    /// Returns the number of maximal runs of consecutive 1 bits in a word,
+    /// e.g. 3 for `0b1101_0011`.
+    ///
+    /// Each run is counted at its lowest bit, the bits `a_i & !a_{i-1}`. The
+    /// bottom bit starts a run whenever it is set, and every other start is
+    /// a select of `a_i` or a constant 0 on `a_{i-1}`, summed with `add`. On
+    /// top of the decomposition's `WORD_BITS + 1` rows and the constant's,
+    /// that is a select and an add for each of the other `WORD_BITS - 1`
+    /// bits at two rows each, so `5 * WORD_BITS - 2` rows in all.
+    fn count_runs(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error>;
+
+    /// Returns the Hamming distance `popcount(a ^ b)`, the number of bits
+    /// `a` and `b` differ in.
+    ///
+    /// The XOR is only available as a pair of spread lanes, and counting
+    /// their bits directly would skip its compose but decompose each lane
+    /// over a full word, twice the bits for two rows saved. So the XOR is
+    /// composed and its word decomposed once, which is `xor` then `popcount`.
+    fn hamming(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns the index of the most significant set bit of a word, counting
+    /// from 0 at the least significant one: `floor(log2(a))`.
+    ///
+    /// Zero has no set bit, and is defined to give 0, the same as 1. Callers
+    /// that need to tell the two apart can check `eq_const(a, 0)` alongside.
+    ///
+    /// The index starts out at 0 and walks up the bit decomposition, with a
+    /// select per bit replacing it by that bit's position whenever the bit is
+    /// set, so the highest set bit has the last word.
+    fn msb_index(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error>;
+
+    /// Constrains a word to be 0 or 1.
+    fn assert_boolean(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<(), Error>;
+
+    /// Constrains a word to be even, and range-checks it.
+    ///
+    /// The low bit out of `decompose_bits` is tied to a constant 0, so this
+    /// is `WORD_BITS + 1` rows plus the constant's. Extracting the bit with
+    /// `bits` and comparing it with `eq_const` would add a compose and an
+    /// is-zero region on top, only to get a boolean back.
+    fn assert_even(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<(), Error>;
+
+    /// Constrains a word to be odd, and range-checks it. The same as
+    /// `assert_even`, with the low bit tied to 1.
+    fn assert_odd(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<(), Error>;
+
+    /// Returns `a` when `cond = 0` and `b` when `cond = 1`. `cond` is
+    /// constrained to be boolean.
+    fn select(
+        &self,
+        layouter: impl Layouter<F>,
+        cond: Self::Word,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns `(a, b)` when `cond = 0` and `(b, a)` when `cond = 1`.
+    ///
+    /// This is two selects sharing a single boolean assertion on `cond`.
+    fn cswap(
+        &self,
+        layouter: impl Layouter<F>,
+        cond: Self::Word,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<(Self::Word, Self::Word), Error>;
+
+    /// Returns `(a & mask) | (b & !mask)`, taking each bit from `a` where
+    /// `mask` is set and from `b` where it isn't: `select` at the bit level.
+    /// Note that a set mask bit picks `a`, the opposite of `select`'s `cond`.
+    ///
+    /// It is computed as `b ^ ((a ^ b) & mask)` in spread form, so each
+    /// operand is decomposed once and its lanes reused: `mask`'s feed the AND
+    /// directly and `b`'s feed the final XOR. That sharing is the whole
+    /// point; chaining word-level `xor`, `and` and `xor` decomposes every
+    /// intermediate again, 42 rows against these 32.
+    fn blend(
+        &self,
+        layouter: impl Layouter<F>,
+        mask: Self::Word,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Loads a constant from the fixed column into the circuit.
+    fn load_constant(&self, layouter: impl Layouter<F>, c: F) -> Result<Self::Word, Error>;
+
+    /// Adds two words modulo `2^WORD_BITS`, returning the sum along with the
+    /// boolean carry out of the top bit. Both words and the sum are
+    /// range-checked.
+    fn add_checked(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<(Self::Word, Self::Word), Error>;
+
+    /// Adds `words` one after the other with `add_checked`, returning the sum
+    /// modulo `2^WORD_BITS` and the total of the carries, i.e. the sum split
+    /// at bit `WORD_BITS`.
+    ///
+    /// Each step carries at most one, so `n` words carry at most `n - 1` and
+    /// the total carry needs `ceil(log2(n))` bits, growing with the length of
+    /// the slice rather than staying boolean. It is range-checked to that
+    /// width, which has to fit in a word. Panics on an empty slice.
+    fn add_chain(
+        &self,
+        layouter: impl Layouter<F>,
+        words: &[Self::Word],
+    ) -> Result<(Self::Word, Self::Word), Error>;
+
+    /// Subtracts the constant `c` from a word modulo `2^WORD_BITS`, returning
+    /// the difference along with an underflow bit that is 1 exactly when
+    /// `a < c`. `a == c` gives zero without underflow.
+    ///
+    /// `c` is fixed through the constants column, and the subtraction is the
+    /// `add carry` gate run as a borrow, `c + diff = a + underflow *
+    /// 2^WORD_BITS`. The gate constrains the underflow bit to be boolean, as
+    /// it does the carry of `add_checked`, and both `a` and the difference
+    /// are range-checked. Panics unless `c < 2^WORD_BITS`.
+    fn sub_const(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        c: u64,
+    ) -> Result<(Self::Word, Self::Word), Error>;
+
+    /// Returns the smallest power of two `>= a`, which is 1 for both 0 and 1.
+    /// Where that power of two doesn't fit in the word, for `a >
+    /// 2^(WORD_BITS - 1)`, the result wraps to 0, as
+    /// `wrapping_next_power_of_two` does. `a` is range-checked.
+    ///
+    /// This is the usual bit smearing, `(a - 1) | (a - 1) >> 1 | ...` then
+    /// `+ 1`, done on bits rather than words. `sub_const` gives `a - 1` and
+    /// its borrow, and the difference is decomposed. Bit `i` of the smear is
+    /// then the OR of every bit of `a - 1` from `i` up. That is a select
+    /// from the bit above onto a constant 1, so the `log2(WORD_BITS)` shifts
+    /// and ORs of whole words become `WORD_BITS - 1` two-row selects. The
+    /// smear is composed, `add_checked` adds the 1, dropping the carry out of
+    /// the overflow case, and a last select on the borrow turns the 0 that
+    /// `a = 0` smears to into 1. In all `4 * WORD_BITS + 18` rows, 50 at 8
+    /// bits.
+    fn next_pow2(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error>;
+
+    /// Returns `min(a + b, 2^WORD_BITS - 1)`.
+    ///
+    /// The carry from `add_checked` picks between the sum and the clamped
+    /// maximum, rather than comparing the sum against the maximum again.
+    fn saturating_add(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns `a & !a`, which is always 0.
+    ///
+    /// The zero comes out of the constraint system rather than the constants
+    /// column, so it is tied to `a`'s cell: an in-circuit zeroing primitive,
+    /// and a self-test of `and` over a word and its complement.
+    fn clear(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error>;
+
+    /// Returns the two's-complement negation `(2^WORD_BITS - a) mod 2^WORD_BITS`,
+    /// computed as `not(a) + 1` and range-checked. This is the building block
+    /// for a `sub`, as `a + neg(b)`.
+    ///
+    /// `not` range-checks its result, so the `+ 1` goes through the `add
+    /// carry` gate with a single range check on the sum, rather than
+    /// `add_checked`'s three.
+    fn neg(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error>;
+
+    /// Returns 1 if `a < b` as unsigned words and 0 otherwise. Both words are
+    /// range-checked.
+    ///
+    /// Bitwise instructions such as `and` and `xor` give the same bits whether
+    /// a word is read as unsigned or as two's complement. Comparisons don't,
+    /// which is why they come in a `_signed` variant.
+    fn lt(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns 1 if `a < b` as two's-complement words, with bit
+    /// `WORD_BITS - 1` as the sign, and 0 otherwise. Both words are
+    /// range-checked.
+    ///
+    /// Flipping the sign bit, which is adding `2^(WORD_BITS - 1)` modulo
+    /// `2^WORD_BITS`, maps the signed order onto the unsigned one, so this is
+    /// `lt` over the flipped words.
+    fn lt_signed(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns `(min(a, b), max(a, b))` as two's-complement words: a `cswap`
+    /// on `lt_signed(b, a)`.
+    fn minmax_signed(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<(Self::Word, Self::Word), Error>;
+
+    /// Returns 1 if `a == c` and 0 otherwise.
+    ///
+    /// `c` is fixed into the circuit through the constants column and compared
+    /// in a single two-row is-zero region over `a - c`, so the verifier knows
+    /// it and, unlike loading it as a private word for `assert_equal`, the
+    /// comparison yields a boolean instead of failing the proof. A `c` at or
+    /// above `2^WORD_BITS` never matches a range-checked `a`.
+    fn eq_const(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        c: u64,
+    ) -> Result<Self::Word, Error>;
+
+    /// Returns 1 if `a` and `b` have no set bit in common, `a & b == 0`, and
+    /// 0 otherwise: the check that two bitmap allocations don't conflict.
+    ///
+    /// This is `and` followed by `eq_const` against 0, the is-zero region
+    /// reading the AND's output cell directly, so 16 rows in all. Both words
+    /// are range-checked by the AND.
+    fn disjoint(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error>;
+
+    /// Constrains two words to be equal. This is a copy constraint between
+    /// their cells and takes no rows.
+    fn assert_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<(), Error>;
+
+    /// Exposes a number as a public input to the circuit, at `row` of the
+    /// `column`th instance column handed to `AndChip::configure`.
+    ///
+    /// The public inputs given to the prover and verifier are one vector per
+    /// instance column, in that same order. A `column` past the last one is
+    /// `Error::Synthesis`.
+    fn expose_public(
+        &self,
+        layouter: impl Layouter<F>,
+        num: Self::Word,
+        column: usize,
+        row: usize,
+    ) -> Result<(), Error>;
+}
+
+/// The chip that will implement our instructions! Chips store their own
+/// config, as well as type markers if necessary.
+pub struct AndChip<F: FieldExt, const WORD_BITS: u32> {
+    config: AndConfig,
+    /// Whether `alloc_table` has already filled the `even_bits` table.
+    table_allocated: Cell<bool>,
+    /// Prepended to the name of every region and table the chip assigns,
+    /// or empty for none.
+    prefix: String,
+    _marker: PhantomData<F>,
+}
+
+/// Chip state is stored in a config struct. This is generated by the chip
+/// during configuration, and then stored inside the chip.
+#[derive(Clone, Debug)]
+pub struct AndConfig {
+    /// For this chip, we will use two advice columns to implement our instructions.
+    /// These are also the columns through which we communicate with other parts of
+    /// the circuit.
+    advice: [Column<Advice>; 2],
+
+    /// These are the public input (instance) columns. Circuits with several
+    /// outputs may spread them over more than one.
+    instance: Vec<Column<Instance>>,
+
+    /// One table column per sub-lane, see `SpreadTable`.
+    even_bits: Vec<TableColumn>,
+    spread_table: SpreadTable,
+
+    // We need a selector to enable the add gate, so that we aren't placing
+    // any constraints on cells where `NumericInstructions::add` is not being used.
+    // This is important when building larger circuits, where columns are used by
+    // multiple sets of instructions.
+    s_add: Selector,
+    s_decompose: Selector,
+    s_compose: Selector,
+    s_bits: Selector,
+    s_bool: Selector,
+    s_select: Selector,
+    s_add_carry: Selector,
+    s_is_equal: Selector,
+
+    /// The AND table and its selector, for the `DirectLookup` backend.
+    and_lookup: Option<AndLookup>,
+}
+
+/// How `NumericInstructions::and` gets from the spread lanes of its operands
+/// to the spread lanes of the result.
+///
+/// `Spread` adds the lanes and decomposes the sums once more, reading the
+/// AND off the odd bits; it needs nothing beyond the `2^(WORD_BITS/2)`-row
+/// `even_bits` table. `DirectLookup` looks each pair of lanes up in a
+/// precomputed `(x_spread, y_spread) -> (x & y)_spread` table instead, which
+/// saves the two adds and two decompositions (4 of the 14 rows of an AND)
+/// but needs a table of `2^WORD_BITS` rows, one per pair of lanes.
+///
+/// The table sets a floor of `k > WORD_BITS` on the circuit, where the
+/// spread backend only needs `k > WORD_BITS / 2`. At 8 bits that is a
+/// 256-row table, cheap enough to be worth it once a circuit does a few
+/// dozen ANDs. At 16 bits the table alone takes `k = 17`, which only pays
+/// off for circuits with tens of thousands of ANDs, and past that the table
+/// outgrows any practical `k`: 16 bits is the crossover beyond which only
+/// `Spread` is viable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableBackend {
+    Spread,
+    DirectLookup,
+}
+
+/// How the `even_bits` table that range-checks the spread lanes is laid out.
+///
+/// `Full` stores the spread of every `WORD_BITS / 2`-bit lane value in a
+/// single table column of `2^(WORD_BITS/2)` rows, and looks each lane up as
+/// it is. Past a few dozen ANDs the advice rows outgrow it, but at 16 bits
+/// and up it is the table that sets `k` for small circuits.
+///
+/// `Columns(n)` trades those rows for table columns. Each lane is split into
+/// `n` sub-lanes of `WORD_BITS / (2n)` bits, and table column `j` holds the
+/// spread of every sub-lane value shifted up to sub-lane `j`'s place in the
+/// lane: `2^(WORD_BITS/(2n))` rows, `n` columns wide. A decompose region
+/// witnesses the sub-lanes of both lanes on `n` rows below the word, looks
+/// sub-lane `j` up in column `j`, and constrains the sub-lanes to add up to
+/// their lane. That checks the same thing `Full` does: a sum of spread
+/// values, each in its own place, is the spread of a `WORD_BITS / 2`-bit
+/// lane.
+///
+/// The rows come back elsewhere. Every decompose region grows from 2 rows to
+/// `n + 2`, and an AND takes four of them, so `Columns(n)` only lowers `k`
+/// while the table is what sets it. At 16 bits `Columns(2)` cuts the table
+/// from 256 rows to 16 and a single AND fits in `k = 5` rather than 9, but
+/// past a dozen or so ANDs the advice rows set `k` again, and past two dozen
+/// the extra ones raise it. It also takes `2n` lookup arguments rather than
+/// 2, each with commitments of its own in the proof. `Columns(1)` is `Full`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpreadTable {
+    Full,
+    Columns(u32),
+}
+
+impl SpreadTable {
+    /// The number of table columns, and of sub-lanes a lane is looked up as.
+    pub fn columns(self) -> u32 {
+        match self {
+            SpreadTable::Full => 1,
+            SpreadTable::Columns(n) => n,
+        }
+    }
+
+    /// The number of rows the `even_bits` table takes for `WORD_BITS`.
+    pub fn rows<const WORD_BITS: u32>(self) -> usize {
+        1 << (WORD_BITS / 2 / self.columns())
+    }
+
+    /// The spread bits of a sub-lane: twice the bits of the sub-lane value.
+    fn sub_lane_bits<const WORD_BITS: u32>(self) -> u32 {
+        WORD_BITS / self.columns()
+    }
+
+    /// The entries `alloc_table` fills table column `column` with, in row
+    /// order.
+    fn entries<F: FieldExt, const WORD_BITS: u32>(self, column: u32) -> impl Iterator<Item = F> {
+        let shift = self.sub_lane_bits::<WORD_BITS>() * column;
+        (0..self.rows::<WORD_BITS>()).map(move |i| F::from_u128((even_bits_at(i) as u128) << shift))
+    }
+
+    /// `lane` split into its sub-lanes, one per column, each left in its
+    /// place so that they add up to `lane`. Bits past the top sub-lane are
+    /// dropped, so the sub-lanes of a lane that doesn't fit don't add up to
+    /// it.
+    fn sub_lanes<F: FieldExt, const WORD_BITS: u32>(self, lane: F) -> Vec<F> {
+        let bits = self.sub_lane_bits::<WORD_BITS>();
+        let lane = lane.get_lower_128();
+        (0..self.columns())
+            .map(|j| F::from_u128(lane & (((1u128 << bits) - 1) << (bits * j))))
+            .collect()
+    }
+}
+
+/// The row of a decompose region that the first sub-lane of a
+/// `SpreadTable::Columns` table sits on, below the lanes and the word.
+const SUB_LANE_ROW: usize = 2;
+
+/// The `DirectLookup` AND table, keyed by the spread lanes of both operands.
+#[derive(Clone, Copy, Debug)]
+struct AndLookup {
+    s_and: Selector,
+    lhs: TableColumn,
+    rhs: TableColumn,
+    out: TableColumn,
+}
+
+impl<F: FieldExt, const WORD_BITS: u32> AndChip<F, WORD_BITS> {
+    pub fn construct(config: <Self as Chip<F>>::Config) -> Self {
+        Self::construct_with_prefix(config, "")
+    }
+
+    /// `construct`, with every region, table and namespace the chip opens
+    /// named `"{prefix}/{name}"`, e.g. `"lhs/decompose"` or
+    /// `"lhs/spread sums"`.
+    ///
+    /// halo2 names regions after the closure passed to `assign_region`
+    /// alone, whatever namespace the layouter is in, and the DOT graph only
+    /// shows namespaces, so a host circuit with several `AndChip`s, or with
+    /// regions of its own called `"add"` or `"decompose"`, can't tell them
+    /// apart in a layout or a DOT graph otherwise. An empty prefix leaves the
+    /// names as they are.
+    pub fn construct_with_prefix(config: <Self as Chip<F>>::Config, prefix: &str) -> Self {
+        Self {
+            config,
+            table_allocated: Cell::new(false),
+            prefix: prefix.to_string(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// `name` under the chip's prefix.
+    fn region_name(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.prefix, name)
+        }
+    }
+
+    /// `construct`, for a config that didn't necessarily come out of
+    /// `configure` on `meta`: one carried over from another constraint
+    /// system, say. Checks that `meta` has equality enabled on every advice
+    /// and instance column of the config and a constants column registered,
+    /// which would otherwise only surface as a copy constraint failing
+    /// during synthesis.
+    pub fn try_construct(
+        meta: &ConstraintSystem<F>,
+        config: <Self as Chip<F>>::Config,
+    ) -> Result<Self, AndError> {
+        let enabled = meta.permutation().get_columns();
+        let columns = config
+            .advice
+            .iter()
+            .map(|column| Column::<Any>::from(*column))
+            .chain(config.instance.iter().map(|column| (*column).into()));
+        for column in columns {
+            if !enabled.contains(&column) {
+                return Err(AndError::EqualityNotEnabled(column));
+            }
+        }
+        if meta.constants().is_empty() {
+            return Err(AndError::NoConstantColumn);
+        }
+        Ok(Self::construct(config))
+    }
+
+    /// Configures the chip over columns the caller has already allocated, so
+    /// that a host circuit can share them with its own chips.
+    ///
+    /// Equality is enabled on both `advice` columns and every `instance`
+    /// column, and `constant` is registered as a constants column. The chip's
+    /// gates are all behind its own selectors, and its lookups behind
+    /// `s_decompose`, so it places no constraints on rows it doesn't use. The
+    /// `even_bits` table column is the only one it allocates itself, and has
+    /// to be filled once with `alloc_table` during synthesis.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 2],
+        instance: Vec<Column<Instance>>,
+        constant: Column<Fixed>,
+    ) -> <Self as Chip<F>>::Config {
+        Self::configure_with_backend(meta, advice, instance, constant, TableBackend::Spread)
+    }
+
+    /// `configure`, with `and` implemented by `backend`. `DirectLookup` adds
+    /// a complex selector and the three columns of the AND table, which
+    /// `alloc_table` fills alongside `even_bits`.
+    pub fn configure_with_backend(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 2],
+        instance: Vec<Column<Instance>>,
+        constant: Column<Fixed>,
+        backend: TableBackend,
+    ) -> <Self as Chip<F>>::Config {
+        Self::configure_with_tables(meta, advice, instance, constant, backend, SpreadTable::Full)
+    }
+
+    /// `configure_with_backend`, with the `even_bits` table laid out as
+    /// `spread_table`.
+    pub fn configure_with_tables(
+        meta: &mut ConstraintSystem<F>,
+        advice: [Column<Advice>; 2],
+        instance: Vec<Column<Instance>>,
+        constant: Column<Fixed>,
+        backend: TableBackend,
+        spread_table: SpreadTable,
+    ) -> <Self as Chip<F>>::Config {
+        // The spread table holds `WORD_BITS / 2`-bit lanes, so a word has to
+        // split evenly into an even and an odd lane of at least one bit each.
+        // A 1-bit word would get a 1-row table that range-checks nothing.
+        assert!(
+            WORD_BITS >= 2 && WORD_BITS % 2 == 0,
+            "AndChip requires an even WORD_BITS of at least 2, got {}",
+            WORD_BITS
+        );
+        let columns = spread_table.columns();
+        assert!(
+            columns >= 1 && (WORD_BITS / 2) % columns == 0,
+            "a {}-bit lane doesn't split into {} sub-lanes",
+            WORD_BITS / 2,
+            columns
+        );
+
+        for column in &instance {
+            meta.enable_equality(*column);
+        }
+        meta.enable_constant(constant);
+        for column in &advice {
+            meta.enable_equality(*column);
+        }
+        let s_add = meta.selector();
+        let s_decompose = meta.complex_selector();
+        let s_compose = meta.selector();
+        let s_bits = meta.selector();
+        let s_bool = meta.selector();
+        let s_select = meta.selector();
+        let s_add_carry = meta.selector();
+        let s_is_equal = meta.selector();
+        let even_bits: Vec<_> = (0..columns).map(|_| meta.lookup_table_column()).collect();
+
+        meta.create_gate("add", |meta| {
+            let lhs = meta.query_advice(advice[0], Rotation::cur());
+            let rhs = meta.query_advice(advice[1], Rotation::cur());
+            let out = meta.query_advice(advice[0], Rotation::next());
+            let s_add = meta.query_selector(s_add);
+
+            // `lhs + rhs = out`, with `out` on the row below the operands.
+            vec![s_add * (lhs + rhs - out)]
+        });
+
+        meta.create_gate("decompose", |meta| {
+            let lhs = meta.query_advice(advice[0], Rotation::cur());
+            let rhs = meta.query_advice(advice[1], Rotation::cur());
+            let out = meta.query_advice(advice[0], Rotation::next());
+            let s_decompose = meta.query_selector(s_decompose);
+
+            // The odd lane is stored shifted down by one bit, so every one of
+            // its bits sits one place below where it belongs in `out`. The 2
+            // shifts it back up; it is pinned by `gate_coefficient_test`.
+            vec![s_decompose * (lhs + Expression::Constant(F::from(2)) * rhs - out)]
+        });
+
+        meta.create_gate("compose", |meta| {
+            let lhs = meta.query_advice(advice[0], Rotation::cur());
+            let rhs = meta.query_advice(advice[1], Rotation::cur());
+            let out = meta.query_advice(advice[0], Rotation::next());
+            let s_compose = meta.query_selector(s_compose);
+
+            // The same even/odd lane weighting as `decompose`, run the other
+            // way.
+            vec![s_compose * (lhs + Expression::Constant(F::from(2)) * rhs - out)]
+        });
+
+        meta.create_gate("bits", |meta| {
+            let acc = meta.query_advice(advice[0], Rotation::cur());
+            let bit = meta.query_advice(advice[1], Rotation::cur());
+            let acc_next = meta.query_advice(advice[0], Rotation::next());
+            let s_bits = meta.query_selector(s_bits);
+
+            // One step of Horner's rule over a boolean bit: the accumulator is
+            // doubled and the bit added in, so bits go in most significant first.
+            vec![
+                s_bits.clone() * bit.clone() * (Expression::Constant(F::one()) - bit.clone()),
+                s_bits * (Expression::Constant(F::from(2)) * acc + bit - acc_next),
+            ]
+        });
+
+        meta.create_gate("bool", |meta| {
+            let a = meta.query_advice(advice[0], Rotation::cur());
+            let s_bool = meta.query_selector(s_bool);
+
+            vec![s_bool * a.clone() * (Expression::Constant(F::one()) - a)]
+        });
+
+        meta.create_gate("select", |meta| {
+            let cond = meta.query_advice(advice[0], Rotation::cur());
+            let a = meta.query_advice(advice[1], Rotation::cur());
+            let b = meta.query_advice(advice[0], Rotation::next());
+            let out = meta.query_advice(advice[1], Rotation::next());
+            let s_select = meta.query_selector(s_select);
+
+            // `out = a` when `cond = 0` and `out = b` when `cond = 1`. That
+            // `cond` is boolean is left to the `bool` gate.
+            vec![s_select * (a.clone() + cond * (b - a) - out)]
+        });
+
+        meta.create_gate("add carry", |meta| {
+            let lhs = meta.query_advice(advice[0], Rotation::cur());
+            let rhs = meta.query_advice(advice[1], Rotation::cur());
+            let out = meta.query_advice(advice[0], Rotation::next());
+            let carry = meta.query_advice(advice[1], Rotation::next());
+            let s_add_carry = meta.query_selector(s_add_carry);
+
+            // `lhs + rhs = out + carry * 2^WORD_BITS` with a boolean carry. It
+            // is up to the instruction to range-check `out`.
+            vec![
+                s_add_carry.clone()
+                    * carry.clone()
+                    * (Expression::Constant(F::one()) - carry.clone()),
+                s_add_carry
+                    * (lhs + rhs
+                        - out
+                        - Expression::Constant(F::from_u128(1 << WORD_BITS)) * carry),
+            ]
+        });
+
+        meta.create_gate("is equal", |meta| {
+            let lhs = meta.query_advice(advice[0], Rotation::cur());
+            let rhs = meta.query_advice(advice[1], Rotation::cur());
+            let inv = meta.query_advice(advice[0], Rotation::next());
+            let out = meta.query_advice(advice[1], Rotation::next());
+            let s_is_equal = meta.query_selector(s_is_equal);
+
+            // The usual is-zero gadget over `lhs - rhs`: if the difference is
+            // nonzero, `out = 0` is forced by the second constraint and the
+            // first then needs `inv` to be its inverse. If it is zero, the
+            // first constraint forces `out = 1`.
+            let diff = lhs - rhs;
+            vec![
+                s_is_equal.clone()
+                    * (Expression::Constant(F::one()) - diff.clone() * inv - out.clone()),
+                s_is_equal * diff * out,
+            ]
+        });
+
+        // Sub-lane `j` of each lane is looked up in table column `j`. In a
+        // `Full` table the only sub-lane is the lane itself, on the decompose
+        // row; split over several columns, the sub-lanes sit on the rows
+        // below the word, from `SUB_LANE_ROW`. With the selector off the
+        // input is 0, which every column holds in its first row.
+        let first_sub_lane = if columns == 1 { 0 } else { SUB_LANE_ROW };
+        for (j, column) in even_bits.iter().enumerate() {
+            let at = Rotation((first_sub_lane + j) as i32);
+            for lane in advice {
+                let _ = meta.lookup(|meta| {
+                    let lookup = meta.query_selector(s_decompose);
+                    let sub_lane = meta.query_advice(lane, at);
+
+                    vec![(lookup * sub_lane, *column)]
+                });
+            }
+        }
+
+        if columns > 1 {
+            meta.create_gate("sub-lanes", |meta| {
+                let s_decompose = meta.query_selector(s_decompose);
+
+                // Each lane is the sum of its sub-lanes, which are already
+                // in place.
+                let mut constraints = vec![];
+                for lane in advice {
+                    let mut remainder = meta.query_advice(lane, Rotation::cur());
+                    for j in 0..columns as usize {
+                        let at = Rotation((SUB_LANE_ROW + j) as i32);
+                        remainder = remainder - meta.query_advice(lane, at);
+                    }
+                    constraints.push(s_decompose.clone() * remainder);
+                }
+                constraints
+            });
+        }
+
+        let and_lookup = match backend {
+            TableBackend::Spread => None,
+            TableBackend::DirectLookup => {
+                let and_lookup = AndLookup {
+                    s_and: meta.complex_selector(),
+                    lhs: meta.lookup_table_column(),
+                    rhs: meta.lookup_table_column(),
+                    out: meta.lookup_table_column(),
+                };
+
+                // Both lanes sit on the current row and their AND below the
+                // first. With the selector off every input is 0, which the
+                // table has at `(0, 0) -> 0`.
+                let _ = meta.lookup(|meta| {
+                    let s_and = meta.query_selector(and_lookup.s_and);
+                    let lhs = meta.query_advice(advice[0], Rotation::cur());
+                    let rhs = meta.query_advice(advice[1], Rotation::cur());
+                    let out = meta.query_advice(advice[0], Rotation::next());
+
+                    vec![
+                        (s_and.clone() * lhs, and_lookup.lhs),
+                        (s_and.clone() * rhs, and_lookup.rhs),
+                        (s_and * out, and_lookup.out),
+                    ]
+                });
+                Some(and_lookup)
+            }
+        };
+
+        AndConfig {
+            advice,
+            instance,
+            even_bits,
+            spread_table,
+            s_add,
+            s_decompose,
+            s_compose,
+            s_bits,
+            s_bool,
+            s_select,
+            s_add_carry,
+            s_is_equal,
+            and_lookup,
+        }
+    }
+
+    // Allocates all even bits in a a table for the word size AND_BITS.
+    // `2^(WORD_BITS/2)` rows of the constraint system: the table holds one
+    // spread per half-word lane value, not one per word, so 16 rows at
+    // `WORD_BITS = 8`. Table columns are fixed columns underneath and their
+    // rows count against `2^k` like any other, minus the unusable ones; see
+    // `min_k_for_vec`. A `SpreadTable::Columns(n)` table fills `n` columns
+    // of `2^(WORD_BITS/(2n))` rows instead. With the `DirectLookup` backend
+    // this also fills the `2^WORD_BITS`-row AND table.
+    //
+    // The table can only be filled once per chip; a second call returns
+    // `AndError::TableAlreadyAllocated` rather than halo2's complaint about
+    // the table column being assigned twice.
+    pub fn alloc_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), AndError> {
+        if self.table_allocated.replace(true) {
+            return Err(AndError::TableAlreadyAllocated);
+        }
+
+        layouter.assign_table(
+            || self.region_name("even bits table"),
+            |mut table| {
+                for (j, column) in self.config.even_bits.iter().enumerate() {
+                    let entries = self.config.spread_table.entries::<F, WORD_BITS>(j as u32);
+                    for (i, entry) in entries.enumerate() {
+                        table.assign_cell(
+                            || format!("even_bits row {}", i),
+                            *column,
+                            i,
+                            || Ok(entry),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        if let Some(and_lookup) = self.config.and_lookup {
+            layouter.assign_table(
+                || self.region_name("and table"),
+                |mut table| {
+                    for (row, entry) in and_table_rows::<WORD_BITS>().enumerate() {
+                        let columns = [and_lookup.lhs, and_lookup.rhs, and_lookup.out];
+                        for (column, value) in columns.into_iter().zip(entry) {
+                            table.assign_cell(
+                                || format!("and table row {}", row),
+                                column,
+                                row,
+                                || Ok(F::from(value)),
+                            )?;
+                        }
+                    }
+                    Ok(())
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The rows of the `DirectLookup` backend's AND table: every pair of spread
+/// lanes of `WORD_BITS / 2` bits, with the spread of their AND.
+fn and_table_rows<const WORD_BITS: u32>() -> impl Iterator<Item = [u64; 3]> {
+    let lanes = 1usize << (WORD_BITS / 2);
+    (0..lanes).flat_map(move |x| {
+        (0..lanes).map(move |y| [x, y, x & y].map(|lane| even_bits_at(lane) as u64))
+    })
+}
+
+fn even_bits_at(mut i: usize) -> usize {
+    let mut r = 0;
+    let mut c = 0;
+
+    while i != 0 {
+        let lower_bit = i % 2;
+        r += lower_bit * 4usize.pow(c);
+        i >>= 1;
+        c += 1;
+    }
+
+    r
+}
+
+/// Spreads the low `bits` bits of `x` out to the even bit positions, with a
+/// zero interleaved above each one: the form the `even_bits` table stores.
+pub fn spread(x: u8, bits: u32) -> u64 {
+    (0..bits).fold(0, |acc, i| acc | (((x as u64) >> i) & 1) << (2 * i))
+}
+
+/// The inverse of `spread`: gathers the even bit positions of `s` back into
+/// a `bits`-bit value, ignoring the odd ones. `bits` is at most 8.
+pub fn despread(s: u64, bits: u32) -> u8 {
+    (0..bits).fold(0, |acc, i| acc | (((s >> (2 * i)) & 1) as u8) << i)
+}
+
+#[test]
+fn spread_test() {
+    assert_eq!(spread(0b1011, 4), 0b01_00_01_01);
+    assert_eq!(spread(0xFF, 4), 0x55);
+    for i in 0..16 {
+        assert_eq!(spread(i as u8, 4), even_bits_at(i) as u64);
+    }
+}
+
+#[test]
+fn spread_despread_test() {
+    run_proptest(ProptestConfig::default(), 0..16u8, |x| {
+        prop_assert_eq!(despread(spread(x, 4), 4), x);
+        Ok(())
+    });
+}
+
+#[test]
+fn even_bits_at_test() {
+    assert_eq!(0b0, even_bits_at(0));
+    assert_eq!(0b1, even_bits_at(1));
+    assert_eq!(0b100, even_bits_at(2));
+    assert_eq!(0b101, even_bits_at(3));
+}
+
+impl<F: FieldExt, const WORD_BITS: u32> Chip<F> for AndChip<F, WORD_BITS> {
+    type Config = AndConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+/// A variable representing a number.
+#[derive(Clone, Debug)]
+pub struct Word<F: FieldExt>(AssignedCell<F, F>);
+
+/// An AND reduction in progress: the even and odd spread lanes of the
+/// accumulator, as `and_seed` and `and_accumulate` leave them. `and_finish`
+/// composes them into a word.
+#[derive(Clone, Debug)]
+pub struct AndAccumulator<W> {
+    even: W,
+    odd: W,
+}
+
+impl<F: FieldExt> Word<F> {
+    /// The word's value, if it is known and below `2^WORD_BITS`.
+    ///
+    /// Values that don't fit give `None` rather than being truncated the way
+    /// `get_lower_128` would.
+    pub fn value_u64<const WORD_BITS: u32>(&self) -> Option<u64> {
+        word_value::<F, WORD_BITS>(self.0.value()?)
+    }
+
+    /// The byte representation of the word's value, if it is known.
+    ///
+    /// Words have no `Eq` or `Hash`, since a word with an unknown value
+    /// can't equal itself; key host-side maps and sets by this instead.
+    pub fn value_key(&self) -> Option<F::Repr> {
+        self.0.value().map(|value| value.to_repr())
+    }
+}
+
+/// `value` as a `WORD_BITS`-bit integer, or `None` if it doesn't fit.
+fn word_value<F: FieldExt, const WORD_BITS: u32>(value: &F) -> Option<u64> {
+    let lower = value.get_lower_128();
+    (F::from_u128(lower) == *value && lower < 1 << WORD_BITS).then(|| lower as u64)
+}
+
+/// Value equality, for host-side bookkeeping such as memoizing loads of the
+/// same constant. Two words are equal when both their values are known and
+/// the same, wherever their cells are. A word whose value is unknown, as
+/// during key generation, equals no word, not even itself.
+///
+/// This is not a constraint: two equal words can still be unrelated cells
+/// as far as the proof is concerned. `NumericInstructions::assert_equal` is
+/// what constrains two cells to hold the same value in the circuit.
+impl<F: FieldExt> PartialEq for Word<F> {
+    fn eq(&self, other: &Self) -> bool {
+        matches!((self.0.value(), other.0.value()), (Some(a), Some(b)) if a == b)
+    }
+}
+
+impl<F: FieldExt, const WORD_BITS: u32> NumericInstructions<F> for AndChip<F, WORD_BITS> {
+    type Word = Word<F>;
+
+    fn load_private(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<Self::Word, Error> {
+        let config = self.config();
+
+        layouter.assign_region(
+            || self.region_name("load private"),
+            |mut region| {
+                region
+                    .assign_advice(
+                        || "private input",
+                        config.advice[0],
+                        0,
+                        || value.ok_or(Error::Synthesis),
+                    )
+                    .map(Word)
+            },
+        )
+    }
+
+    fn add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let config = self.config();
+
+        layouter.assign_region(
+            || self.region_name("add"),
+            |mut region: Region<'_, F>| {
+                // One add gate, spanning offsets 0 and 1.
+                config.s_add.enable(&mut region, 0)?;
+
+                // The inputs we've been given could be located anywhere in the circuit,
+                // but we can only rely on relative offsets inside this region. So we
+                // assign new cells inside the region and constrain them to have the
+                // same values as the inputs.
+                a.0.copy_advice(|| "lhs", &mut region, config.advice[0], 0)?;
+                b.0.copy_advice(|| "rhs", &mut region, config.advice[1], 0)?;
+
+                // Now we can assign the addition result, which is to be assigned
+                // into the output position.
+                let value = a.0.value().and_then(|a| b.0.value().map(|b| *a + *b));
+
+                // Finally, we do the assignment to the output, returning a
+                // variable to be used in another part of the circuit.
+                region
+                    .assign_advice(
+                        || "lhs + rhs",
+                        config.advice[0],
+                        1,
+                        || value.ok_or(Error::Synthesis),
+                    )
+                    .map(Word)
+            },
+        )
+    }
+
+    fn verify_decompose(
+        &self,
+        mut layouter: impl Layouter<F>,
+        c: Self::Word,
+    ) -> Result<(Self::Word, Self::Word), Error> {
+        let config = self.config();
+
+        layouter.assign_region(
+            || self.region_name("decompose"),
+            |mut region: Region<'_, F>| {
+                // One decompose gate, spanning offsets 0 and 1.
+                config.s_decompose.enable(&mut region, 0)?;
+
+                let o_oe = c.0.value().cloned().map(decompose);
+                let e_cell = region
+                    .assign_advice(
+                        || "even bits",
+                        config.advice[0],
+                        0,
+                        || o_oe.map(|oe| oe.0).ok_or(Error::Synthesis),
+                    )
+                    .map(Word)?;
+
+                let o_cell = region
+                    .assign_advice(
+                        || "odd bits",
+                        config.advice[1],
+                        0,
+                        || o_oe.map(|oe| oe.1).ok_or(Error::Synthesis),
+                    )
+                    .map(Word)?;
+
+                // The inputs we've been given could be located anywhere in the circuit,
+                // but we can only rely on relative offsets inside this region. So we
+                // assign new cells inside the region and constrain them to have the
+                // same values as the inputs.
+                c.0.copy_advice(|| "out", &mut region, config.advice[0], 1)?;
+
+                // Split over several table columns, each lane is looked up as
+                // sub-lanes on the rows below.
+                let spread_table = config.spread_table;
+                if spread_table.columns() > 1 {
+                    let lanes = [o_oe.map(|oe| oe.0), o_oe.map(|oe| oe.1)];
+                    for (column, lane) in config.advice.iter().zip(lanes) {
+                        let sub_lanes =
+                            lane.map(|lane| spread_table.sub_lanes::<F, WORD_BITS>(lane));
+                        for j in 0..spread_table.columns() as usize {
+                            region.assign_advice(
+                                || format!("sub-lane {}", j),
+                                *column,
+                                SUB_LANE_ROW + j,
+                                || sub_lanes.as_ref().map(|s| s[j]).ok_or(Error::Synthesis),
+                            )?;
+                        }
+                    }
+                }
+                Ok((e_cell, o_cell))
+            },
+        )
+    }
+
+    fn compose(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let config = self.config();
+
+        layouter.assign_region(
+            || self.region_name("compose"),
+            |mut region: Region<'_, F>| {
+                config.s_compose.enable(&mut region, 0)?;
+                a.0.copy_advice(|| "lhs", &mut region, config.advice[0], 0)?;
+                b.0.copy_advice(|| "rhs", &mut region, config.advice[1], 0)?;
+                let value =
+                    a.0.value()
+                        .and_then(|a| b.0.value().map(|b| *a + F::from(2) * *b));
+
+                region
+                    .assign_advice(
+                        || "lhs + rhs",
+                        config.advice[0],
+                        1,
+                        || value.ok_or(Error::Synthesis),
+                    )
+                    .map(Word)
+            },
+        )
+    }
+
+    fn and(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        if let Some(and_lookup) = self.config().and_lookup {
+            let (ae, ao) = self.verify_decompose(
+                layouter.namespace(|| self.region_name("a decomposition")),
+                a,
+            )?;
+            let (be, bo) = self.verify_decompose(
+                layouter.namespace(|| self.region_name("b decomposition")),
+                b,
+            )?;
+
+            let even_and = self.lane_and(
+                layouter.namespace(|| self.region_name("ae & be")),
+                and_lookup,
+                ae,
+                be,
+            )?;
+            let odd_and = self.lane_and(
+                layouter.namespace(|| self.region_name("ao & bo")),
+                and_lookup,
+                ao,
+                bo,
+            )?;
+            return self.compose(
+                layouter.namespace(|| self.region_name("compose ea and oa")),
+                even_and,
+                odd_and,
+            );
+        }
+
+        let sums =
+            self.spread_sums(layouter.namespace(|| self.region_name("spread sums")), a, b)?;
+        self.compose(
+            layouter.namespace(|| self.region_name("compose eo and oo")),
+            sums.even_and,
+            sums.odd_and,
+        )
+    }
+
+    fn and_seed(
+        &self,
+        mut layouter: impl Layouter<F>,
+        seed: Self::Word,
+    ) -> Result<AndAccumulator<Self::Word>, Error> {
+        let (even, odd) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("seed decomposition")),
+            seed,
+        )?;
+        Ok(AndAccumulator { even, odd })
+    }
+
+    fn and_accumulate(
+        &self,
+        mut layouter: impl Layouter<F>,
+        acc: AndAccumulator<Self::Word>,
+        word: Self::Word,
+    ) -> Result<AndAccumulator<Self::Word>, Error> {
+        let AndAccumulator { even, odd } = acc;
+        let (we, wo) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("word decomposition")),
+            word,
+        )?;
+
+        if let Some(and_lookup) = self.config().and_lookup {
+            let even = self.lane_and(
+                layouter.namespace(|| self.region_name("even & we")),
+                and_lookup,
+                even,
+                we,
+            )?;
+            let odd = self.lane_and(
+                layouter.namespace(|| self.region_name("odd & wo")),
+                and_lookup,
+                odd,
+                wo,
+            )?;
+            return Ok(AndAccumulator { even, odd });
+        }
+
+        // The AND lanes of a sum come out of its decomposition as spread
+        // lanes again, ready for the next word.
+        let e = self.add(
+            layouter.namespace(|| self.region_name("even + we")),
+            even,
+            we,
+        )?;
+        let o = self.add(layouter.namespace(|| self.region_name("odd + wo")), odd, wo)?;
+        let (_, even) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("e decomposition")),
+            e,
+        )?;
+        let (_, odd) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("o decomposition")),
+            o,
+        )?;
+        Ok(AndAccumulator { even, odd })
+    }
+
+    fn and_finish(
+        &self,
+        mut layouter: impl Layouter<F>,
+        acc: AndAccumulator<Self::Word>,
+    ) -> Result<Self::Word, Error> {
+        self.compose(
+            layouter.namespace(|| self.region_name("compose accumulator")),
+            acc.even,
+            acc.odd,
+        )
+    }
+
+    fn and_all(
+        &self,
+        mut layouter: impl Layouter<F>,
+        words: &[Self::Word],
+    ) -> Result<Self::Word, Error> {
+        let (first, rest) = words.split_first().expect("and_all needs a word");
+        if rest.is_empty() {
+            self.verify_decompose(
+                layouter.namespace(|| self.region_name("range check")),
+                first.clone(),
+            )?;
+            return Ok(first.clone());
+        }
+
+        rest.iter()
+            .enumerate()
+            .try_fold(first.clone(), |acc, (i, word)| {
+                self.and(
+                    layouter.namespace(|| self.region_name(&format!("& word {}", i + 1))),
+                    acc,
+                    word.clone(),
+                )
+            })
+    }
+
+    fn column_and(
+        &self,
+        mut layouter: impl Layouter<F>,
+        rows: &[Self::Word],
+    ) -> Result<Self::Word, Error> {
+        let (first, rest) = rows.split_first().expect("column_and needs a row");
+        if rest.is_empty() {
+            self.verify_decompose(
+                layouter.namespace(|| self.region_name("range check")),
+                first.clone(),
+            )?;
+            return Ok(first.clone());
+        }
+
+        let seed = self.and_seed(
+            layouter.namespace(|| self.region_name("row 0")),
+            first.clone(),
+        )?;
+        let acc = rest.iter().enumerate().try_fold(seed, |acc, (i, row)| {
+            self.and_accumulate(
+                layouter.namespace(|| self.region_name(&format!("& row {}", i + 1))),
+                acc,
+                row.clone(),
+            )
+        })?;
+        self.and_finish(
+            layouter.namespace(|| self.region_name("compose column and")),
+            acc,
+        )
+    }
+
+    fn xor(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let sums =
+            self.spread_sums(layouter.namespace(|| self.region_name("spread sums")), a, b)?;
+        self.compose(
+            layouter.namespace(|| self.region_name("compose ee and oe")),
+            sums.even_xor,
+            sums.odd_xor,
+        )
+    }
+
+    fn xor_fold(
+        &self,
+        mut layouter: impl Layouter<F>,
+        words: &[Self::Word],
+    ) -> Result<Self::Word, Error> {
+        let (first, rest) = match words.split_first() {
+            Some(split) => split,
+            None => {
+                return self
+                    .load_constant(layouter.namespace(|| self.region_name("zero")), F::zero())
+            }
+        };
+        if rest.is_empty() {
+            self.verify_decompose(
+                layouter.namespace(|| self.region_name("range check")),
+                first.clone(),
+            )?;
+            return Ok(first.clone());
+        }
+
+        let lanes = self.verify_decompose(
+            layouter.namespace(|| self.region_name("word 0 decomposition")),
+            first.clone(),
+        )?;
+        let (even, odd) = rest.iter().enumerate().try_fold(
+            lanes,
+            |(even, odd), (i, word)| -> Result<_, Error> {
+                let mut layouter =
+                    layouter.namespace(|| self.region_name(&format!("^ word {}", i + 1)));
+                let (we, wo) = self.verify_decompose(
+                    layouter.namespace(|| self.region_name("word decomposition")),
+                    word.clone(),
+                )?;
+
+                // The XOR lanes of a sum are the low bits of its 2-bit lanes,
+                // and come out of its decomposition as spread lanes again.
+                let e = self.add(
+                    layouter.namespace(|| self.region_name("even + we")),
+                    even,
+                    we,
+                )?;
+                let o = self.add(layouter.namespace(|| self.region_name("odd + wo")), odd, wo)?;
+                let (even, _) = self.verify_decompose(
+                    layouter.namespace(|| self.region_name("e decomposition")),
+                    e,
+                )?;
+                let (odd, _) = self.verify_decompose(
+                    layouter.namespace(|| self.region_name("o decomposition")),
+                    o,
+                )?;
+                Ok((even, odd))
+            },
+        )?;
+
+        self.compose(
+            layouter.namespace(|| self.region_name("compose xor fold")),
+            even,
+            odd,
+        )
+    }
+
+    fn not(&self, mut layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error> {
+        let config = self.config();
+        let mask = (1u128 << WORD_BITS) - 1;
+
+        let out = layouter.assign_region(
+            || self.region_name("not"),
+            |mut region: Region<'_, F>| {
+                config.s_add.enable(&mut region, 0)?;
+
+                let value = a.0.value().map(|a| F::from_u128(mask) - a);
+                let out = region.assign_advice(
+                    || "out",
+                    config.advice[0],
+                    0,
+                    || value.ok_or(Error::Synthesis),
+                )?;
+                a.0.copy_advice(|| "a", &mut region, config.advice[1], 0)?;
+                region.assign_advice_from_constant(
+                    || "mask",
+                    config.advice[0],
+                    1,
+                    F::from_u128(mask),
+                )?;
+                Ok(Word(out))
+            },
+        )?;
+
+        self.verify_decompose(
+            layouter.namespace(|| self.region_name("range check out")),
+            out.clone(),
+        )?;
+        Ok(out)
+    }
+
+    fn decompose_bits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+    ) -> Result<Vec<Self::Word>, Error> {
+        let config = self.config();
+
+        layouter.assign_region(
+            || self.region_name("decompose bits"),
+            |mut region: Region<'_, F>| {
+                let value = a.0.value().map(|a| a.get_lower_128());
+
+                let mut acc = region.assign_advice_from_constant(
+                    || "acc 0",
+                    config.advice[0],
+                    0,
+                    F::zero(),
+                )?;
+                let mut bits = Vec::with_capacity(WORD_BITS as usize);
+                for row in 0..WORD_BITS as usize {
+                    config.s_bits.enable(&mut region, row)?;
+
+                    let shift = WORD_BITS as usize - 1 - row;
+                    let bit = region.assign_advice(
+                        || format!("bit {}", shift),
+                        config.advice[1],
+                        row,
+                        || {
+                            value
+                                .map(|v| F::from(((v >> shift) & 1) as u64))
+                                .ok_or(Error::Synthesis)
+                        },
+                    )?;
+
+                    // The last step has to land on the word itself, which is
+                    // what ties the bits to it.
+                    acc = if shift == 0 {
+                        a.0.copy_advice(|| "word", &mut region, config.advice[0], row + 1)?
+                    } else {
+                        let value = acc
+                            .value()
+                            .zip(bit.value())
+                            .map(|(acc, bit)| F::from(2) * *acc + *bit);
+                        region.assign_advice(
+                            || format!("acc {}", row + 1),
+                            config.advice[0],
+                            row + 1,
+                            || value.ok_or(Error::Synthesis),
+                        )?
+                    };
+                    bits.push(Word(bit));
+                }
+
+                bits.reverse();
+                Ok(bits)
+            },
+        )
+    }
+
+    fn compose_bits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bits: &[Self::Word],
+    ) -> Result<Self::Word, Error> {
+        let config = self.config();
+
+        layouter.assign_region(
+            || self.region_name("compose bits"),
+            |mut region: Region<'_, F>| {
+                let mut acc = region.assign_advice_from_constant(
+                    || "acc 0",
+                    config.advice[0],
+                    0,
+                    F::zero(),
+                )?;
+                for (row, bit) in bits.iter().rev().enumerate() {
+                    config.s_bits.enable(&mut region, row)?;
+
+                    let bit = bit.0.copy_advice(
+                        || format!("bit {}", bits.len() - 1 - row),
+                        &mut region,
+                        config.advice[1],
+                        row,
+                    )?;
+                    let value = acc
+                        .value()
+                        .zip(bit.value())
+                        .map(|(acc, bit)| F::from(2) * *acc + *bit);
+                    acc = region.assign_advice(
+                        || format!("acc {}", row + 1),
+                        config.advice[0],
+                        row + 1,
+                        || value.ok_or(Error::Synthesis),
+                    )?;
+                }
+
+                Ok(Word(acc))
+            },
+        )
+    }
+
+    fn bits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        lo: u32,
+        hi: u32,
+    ) -> Result<Self::Word, Error> {
+        assert!(
+            lo <= hi && hi <= WORD_BITS,
+            "bit range {}..{} is outside a {}-bit word",
+            lo,
+            hi,
+            WORD_BITS
+        );
+
+        let bits = self.decompose_bits(layouter.namespace(|| self.region_name("decompose")), a)?;
+        self.compose_bits(
+            layouter.namespace(|| self.region_name(&format!("compose bits {}..{}", lo, hi))),
+            &bits[lo as usize..hi as usize],
+        )
+    }
+
+    fn shr(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        n: u32,
+    ) -> Result<Self::Word, Error> {
+        let n = std::cmp::min(n, WORD_BITS);
+        self.bits(
+            layouter.namespace(|| self.region_name(&format!(">> {}", n))),
+            a,
+            n,
+            WORD_BITS,
+        )
+    }
+
+    fn shl_var(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        shift: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let stages = u32::BITS - (WORD_BITS - 1).leading_zeros();
+
+        // `2^stages` can be past `WORD_BITS` when that isn't a power of two,
+        // so the stage bits alone don't bound `shift` tightly enough.
+        let (_, in_range) = self.sub_const(
+            layouter.namespace(|| self.region_name("shift - WORD_BITS")),
+            shift.clone(),
+            WORD_BITS as u64,
+        )?;
+        let one = self.load_constant(layouter.namespace(|| self.region_name("1")), F::one())?;
+        self.assert_equal(
+            layouter.namespace(|| self.region_name("shift < WORD_BITS")),
+            in_range,
+            one,
+        )?;
+
+        let shift_bits = self.decompose_bits(
+            layouter.namespace(|| self.region_name("decompose shift")),
+            shift,
+        )?;
+        let zero = self.load_constant(layouter.namespace(|| self.region_name("0")), F::zero())?;
+        let mut bits =
+            self.decompose_bits(layouter.namespace(|| self.region_name("decompose a")), a)?;
+
+        // The shift bits come out of `decompose_bits` boolean, so `mux` can
+        // skip the check.
+        for (stage, select) in shift_bits[..stages as usize].iter().enumerate() {
+            let n = 1 << stage;
+            bits = (0..bits.len())
+                .map(|i| {
+                    let shifted = if i >= n {
+                        bits[i - n].clone()
+                    } else {
+                        zero.clone()
+                    };
+                    self.mux(
+                        layouter
+                            .namespace(|| self.region_name(&format!("stage {} bit {}", stage, i))),
+                        select.clone(),
+                        bits[i].clone(),
+                        shifted,
+                    )
+                })
+                .collect::<Result<_, _>>()?;
+        }
+
+        self.compose_bits(
+            layouter.namespace(|| self.region_name("compose shifted")),
+            &bits,
+        )
+    }
+
+    fn mod_pow2(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        bits: u32,
+    ) -> Result<Self::Word, Error> {
+        let bits = std::cmp::min(bits, WORD_BITS);
+        self.bits(
+            layouter.namespace(|| self.region_name(&format!("mod 2^{}", bits))),
+            a,
+            0,
+            bits,
+        )
+    }
+
+    fn to_gray(&self, mut layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error> {
+        let shifted = self.shr(
+            layouter.namespace(|| self.region_name("a >> 1")),
+            a.clone(),
+            1,
+        )?;
+        self.xor(
+            layouter.namespace(|| self.region_name("a ^ (a >> 1)")),
+            a,
+            shifted,
+        )
+    }
+
+    fn from_gray(
+        &self,
+        mut layouter: impl Layouter<F>,
+        g: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let mut acc = g;
+        let mut shift = 1;
+        while shift < WORD_BITS {
+            let shifted = self.shr(
+                layouter.namespace(|| self.region_name(&format!("acc >> {}", shift))),
+                acc.clone(),
+                shift,
+            )?;
+            acc = self.xor(
+                layouter.namespace(|| self.region_name(&format!("acc ^ (acc >> {})", shift))),
+                acc,
+                shifted,
+            )?;
+            shift *= 2;
+        }
+        Ok(acc)
+    }
+
+    fn zero_extend(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        from_bits: u32,
+    ) -> Result<Self::Word, Error> {
+        let low = self.bits(
+            layouter.namespace(|| self.region_name("low bits")),
+            a.clone(),
+            0,
+            from_bits,
+        )?;
+        self.assert_equal(
+            layouter.namespace(|| self.region_name("no high bits")),
+            low.clone(),
+            a,
+        )?;
+        Ok(low)
+    }
+
+    fn sign_extend(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        from_bits: u32,
+    ) -> Result<Self::Word, Error> {
+        assert!(
+            (1..=WORD_BITS).contains(&from_bits),
+            "can't sign-extend a {}-bit word to {} bits",
+            from_bits,
+            WORD_BITS
+        );
+
+        let bits = self.decompose_bits(
+            layouter.namespace(|| self.region_name("decompose")),
+            a.clone(),
+        )?;
+        let from_bits = from_bits as usize;
+
+        let low = self.compose_bits(
+            layouter.namespace(|| self.region_name("low bits")),
+            &bits[..from_bits],
+        )?;
+        self.assert_equal(
+            layouter.namespace(|| self.region_name("no high bits")),
+            low,
+            a,
+        )?;
+
+        let sign = &bits[from_bits - 1];
+        let extended: Vec<_> = bits[..from_bits]
+            .iter()
+            .chain(std::iter::repeat(sign).take(bits.len() - from_bits))
+            .cloned()
+            .collect();
+        self.compose_bits(
+            layouter.namespace(|| self.region_name("extended")),
+            &extended,
+        )
+    }
+
+    fn set_bit(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        index: Self::Word,
+        value: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        self.assert_boolean(
+            layouter.namespace(|| self.region_name("value")),
+            value.clone(),
+        )?;
+
+        let flags = (0..WORD_BITS)
+            .map(|i| {
+                self.eq_const(
+                    layouter.namespace(|| self.region_name(&format!("index == {}", i))),
+                    index.clone(),
+                    i as u64,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let count =
+            flags[1..]
+                .iter()
+                .enumerate()
+                .try_fold(flags[0].clone(), |count, (i, flag)| {
+                    self.add(
+                        layouter.namespace(|| self.region_name(&format!("+ flag {}", i + 1))),
+                        count,
+                        flag.clone(),
+                    )
+                })?;
+        let one = self.load_constant(layouter.namespace(|| self.region_name("one")), F::one())?;
+        self.assert_equal(
+            layouter.namespace(|| self.region_name("index < WORD_BITS")),
+            count,
+            one,
+        )?;
+
+        let bits = self.decompose_bits(layouter.namespace(|| self.region_name("decompose")), a)?;
+        let bits = bits
+            .into_iter()
+            .zip(flags)
+            .enumerate()
+            .map(|(i, (bit, flag))| {
+                // The `is equal` gate only lets flags be 0 or 1.
+                self.mux(
+                    layouter.namespace(|| self.region_name(&format!("bit {}", i))),
+                    flag,
+                    bit,
+                    value.clone(),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.compose_bits(layouter.namespace(|| self.region_name("compose")), &bits)
+    }
+
+    fn morton(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: Self::Word,
+        y: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let x_bits = self.decompose_bits(layouter.namespace(|| self.region_name("x bits")), x)?;
+        let y_bits = self.decompose_bits(layouter.namespace(|| self.region_name("y bits")), y)?;
+
+        let interleaved: Vec<_> = x_bits
+            .into_iter()
+            .zip(y_bits)
+            .flat_map(|(x, y)| [x, y])
+            .collect();
+        self.compose_bits(
+            layouter.namespace(|| self.region_name("interleave")),
+            &interleaved,
+        )
+    }
+
+    fn rotl(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        n: u32,
+    ) -> Result<Self::Word, Error> {
+        let bits = self.decompose_bits(layouter.namespace(|| self.region_name("decompose")), a)?;
+
+        // Bit `i` of the result is bit `i - n` of `a`, wrapping around.
+        let n = (n % WORD_BITS) as usize;
+        let rotated: Vec<_> = (0..bits.len())
+            .map(|i| bits[(i + bits.len() - n) % bits.len()].clone())
+            .collect();
+
+        self.compose_bits(
+            layouter.namespace(|| self.region_name("compose rotated")),
+            &rotated,
+        )
+    }
+
+    fn and_rotated(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        k: u32,
+    ) -> Result<Self::Word, Error> {
+        if k % WORD_BITS == 0 {
+            self.verify_decompose(
+                layouter.namespace(|| self.region_name("range check a")),
+                a.clone(),
+            )?;
+            return Ok(a);
+        }
+
+        let rotated = self.rotl(
+            layouter.namespace(|| self.region_name(&format!("rotl {}", k))),
+            a.clone(),
+            k,
+        )?;
+        self.and(
+            layouter.namespace(|| self.region_name("a & rotl(a)")),
+            a,
+            rotated,
+        )
+    }
+
+    fn theta_lane(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+        c: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let b = self.rotl(layouter.namespace(|| self.region_name("rotl(b, 1)")), b, 1)?;
+        let a_xor_b = self.xor(
+            layouter.namespace(|| self.region_name("a ^ rotl(b, 1)")),
+            a,
+            b,
+        )?;
+        self.xor(
+            layouter.namespace(|| self.region_name("a ^ rotl(b, 1) ^ c")),
+            a_xor_b,
+            c,
+        )
+    }
+
+    fn majority(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+        c: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let (ae, ao) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("a decomposition")),
+            a,
+        )?;
+        let (be, bo) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("b decomposition")),
+            b,
+        )?;
+        let (ce, co) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("c decomposition")),
+            c,
+        )?;
+
+        let e = self.add(layouter.namespace(|| self.region_name("ae + be")), ae, be)?;
+        let e = self.add(
+            layouter.namespace(|| self.region_name("ae + be + ce")),
+            e,
+            ce,
+        )?;
+        let o = self.add(layouter.namespace(|| self.region_name("ao + bo")), ao, bo)?;
+        let o = self.add(
+            layouter.namespace(|| self.region_name("ao + bo + co")),
+            o,
+            co,
+        )?;
+
+        // Lanes sum to at most 3, so they don't carry into each other.
+        let (_, e_maj) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("e decomposition")),
+            e,
+        )?;
+        let (_, o_maj) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("o decomposition")),
+            o,
+        )?;
+
+        self.compose(
+            layouter.namespace(|| self.region_name("compose e_maj and o_maj")),
+            e_maj,
+            o_maj,
+        )
+    }
+
+    fn parity(&self, mut layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error> {
+        let mut bits = self
+            .decompose_bits(layouter.namespace(|| self.region_name("decompose")), a)?
+            .into_iter();
+
+        let first = bits.next().expect("WORD_BITS is at least 2");
+        bits.enumerate().try_fold(first, |acc, (i, bit)| {
+            self.xor(
+                layouter.namespace(|| self.region_name(&format!("^ bit {}", i + 1))),
+                acc,
+                bit,
+            )
+        })
+    }
+
+    fn popcount(&self, mut layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error> {
+        let mut bits = self
+            .decompose_bits(layouter.namespace(|| self.region_name("decompose")), a)?
+            .into_iter();
+
+        let first = bits.next().expect("WORD_BITS is at least 2");
+        bits.enumerate().try_fold(first, |acc, (i, bit)| {
+            self.add(
+                layouter.namespace(|| self.region_name(&format!("+ bit {}", i + 1))),
+                acc,
+                bit,
+            )
+        })
+    }
+
+    fn count_runs(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let bits = self.decompose_bits(layouter.namespace(|| self.region_name("decompose")), a)?;
+        let zero = self.load_constant(layouter.namespace(|| self.region_name("0")), F::zero())?;
+
+        // The bits are boolean already, so `mux` can skip the check.
+        bits.windows(2)
+            .enumerate()
+            .try_fold(bits[0].clone(), |acc, (i, pair)| {
+                let start = self.mux(
+                    layouter
+                        .namespace(|| self.region_name(&format!("run starts at bit {}", i + 1))),
+                    pair[0].clone(),
+                    pair[1].clone(),
+                    zero.clone(),
+                )?;
+                self.add(
+                    layouter.namespace(|| self.region_name(&format!("+ start {}", i + 1))),
+                    acc,
+                    start,
+                )
+            })
+    }
+
+    fn hamming(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let a_xor_b = self.xor(layouter.namespace(|| self.region_name("a ^ b")), a, b)?;
+        self.popcount(
+            layouter.namespace(|| self.region_name("popcount(a ^ b)")),
+            a_xor_b,
+        )
+    }
+
+    fn msb_index(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let bits = self.decompose_bits(layouter.namespace(|| self.region_name("decompose")), a)?;
+
+        // Bit 0 would only replace the initial 0 by another 0.
+        let zero = self.load_constant(
+            layouter.namespace(|| self.region_name("index 0")),
+            F::zero(),
+        )?;
+        bits.into_iter()
+            .enumerate()
+            .skip(1)
+            .try_fold(zero, |index, (i, bit)| {
+                let position = self.load_constant(
+                    layouter.namespace(|| self.region_name(&format!("index {}", i))),
+                    F::from(i as u64),
+                )?;
+                // `decompose_bits` has already constrained the bits to be
+                // boolean, so the check `select` adds would be redundant.
+                self.mux(
+                    layouter.namespace(|| self.region_name(&format!("bit {}", i))),
+                    bit,
+                    index,
+                    position,
+                )
+            })
+    }
+
+    fn assert_boolean(&self, mut layouter: impl Layouter<F>, a: Self::Word) -> Result<(), Error> {
+        let config = self.config();
+
+        layouter.assign_region(
+            || self.region_name("assert boolean"),
+            |mut region: Region<'_, F>| {
+                config.s_bool.enable(&mut region, 0)?;
+                a.0.copy_advice(|| "a", &mut region, config.advice[0], 0)?;
+                Ok(())
+            },
+        )
+    }
+
+    fn assert_even(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<(), Error> {
+        self.assert_low_bit(layouter, a, false)
+    }
+
+    fn assert_odd(&self, layouter: impl Layouter<F>, a: Self::Word) -> Result<(), Error> {
+        self.assert_low_bit(layouter, a, true)
+    }
+
+    fn select(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cond: Self::Word,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        self.assert_boolean(
+            layouter.namespace(|| self.region_name("cond")),
+            cond.clone(),
+        )?;
+        self.mux(layouter.namespace(|| self.region_name("mux")), cond, a, b)
+    }
+
+    fn cswap(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cond: Self::Word,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<(Self::Word, Self::Word), Error> {
+        self.assert_boolean(
+            layouter.namespace(|| self.region_name("cond")),
+            cond.clone(),
+        )?;
+        let first = self.mux(
+            layouter.namespace(|| self.region_name("first")),
+            cond.clone(),
+            a.clone(),
+            b.clone(),
+        )?;
+        let second = self.mux(
+            layouter.namespace(|| self.region_name("second")),
+            cond,
+            b,
+            a,
+        )?;
+        Ok((first, second))
+    }
+
+    fn blend(
+        &self,
+        mut layouter: impl Layouter<F>,
+        mask: Self::Word,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let (me, mo) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("mask decomposition")),
+            mask,
+        )?;
+        let (ae, ao) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("a decomposition")),
+            a,
+        )?;
+        let (be, bo) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("b decomposition")),
+            b,
+        )?;
+
+        // Every step below sums two spread words, so the lanes hold at most
+        // 2 and don't carry into each other.
+        let e = self.add(
+            layouter.namespace(|| self.region_name("ae + be")),
+            ae,
+            be.clone(),
+        )?;
+        let o = self.add(
+            layouter.namespace(|| self.region_name("ao + bo")),
+            ao,
+            bo.clone(),
+        )?;
+        let (e_diff, _) =
+            self.verify_decompose(layouter.namespace(|| self.region_name("a ^ b even")), e)?;
+        let (o_diff, _) =
+            self.verify_decompose(layouter.namespace(|| self.region_name("a ^ b odd")), o)?;
+
+        let e = self.add(
+            layouter.namespace(|| self.region_name("e_diff + me")),
+            e_diff,
+            me,
+        )?;
+        let o = self.add(
+            layouter.namespace(|| self.region_name("o_diff + mo")),
+            o_diff,
+            mo,
+        )?;
+        let (_, e_flip) =
+            self.verify_decompose(layouter.namespace(|| self.region_name("& mask even")), e)?;
+        let (_, o_flip) =
+            self.verify_decompose(layouter.namespace(|| self.region_name("& mask odd")), o)?;
+
+        let e = self.add(
+            layouter.namespace(|| self.region_name("e_flip + be")),
+            e_flip,
+            be,
+        )?;
+        let o = self.add(
+            layouter.namespace(|| self.region_name("o_flip + bo")),
+            o_flip,
+            bo,
+        )?;
+        let (e_blend, _) =
+            self.verify_decompose(layouter.namespace(|| self.region_name("^ b even")), e)?;
+        let (o_blend, _) =
+            self.verify_decompose(layouter.namespace(|| self.region_name("^ b odd")), o)?;
+
+        self.compose(
+            layouter.namespace(|| self.region_name("compose e_blend and o_blend")),
+            e_blend,
+            o_blend,
+        )
+    }
+
+    fn load_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        column: usize,
+        row: usize,
+    ) -> Result<Self::Word, Error> {
+        let config = self.config();
+        let instance = *config.instance.get(column).ok_or(Error::Synthesis)?;
+
+        layouter.assign_region(
+            || self.region_name("load public"),
+            |mut region| {
+                region
+                    .assign_advice_from_instance(
+                        || "public input",
+                        instance,
+                        row,
+                        config.advice[0],
+                        0,
+                    )
+                    .map(Word)
+            },
+        )
+    }
+
+    fn load_constant(&self, mut layouter: impl Layouter<F>, c: F) -> Result<Self::Word, Error> {
+        let config = self.config();
+
+        layouter.assign_region(
+            || self.region_name("load constant"),
+            |mut region| {
+                region
+                    .assign_advice_from_constant(|| "constant", config.advice[0], 0, c)
+                    .map(Word)
+            },
+        )
+    }
+
+    fn add_checked(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<(Self::Word, Self::Word), Error> {
+        // The gate only sees `a + b`, so an operand past `2^WORD_BITS` would
+        // carry as if it were in range: `300 + 0` splits into a sum of 44
+        // and a carry of 1.
+        self.verify_decompose(
+            layouter.namespace(|| self.region_name("range check a")),
+            a.clone(),
+        )?;
+        self.verify_decompose(
+            layouter.namespace(|| self.region_name("range check b")),
+            b.clone(),
+        )?;
+
+        self.add_carry(layouter, a, b)
+    }
+
+    fn add_chain(
+        &self,
+        mut layouter: impl Layouter<F>,
+        words: &[Self::Word],
+    ) -> Result<(Self::Word, Self::Word), Error> {
+        let (first, rest) = words.split_first().expect("add_chain needs a word");
+        let carry_bits = usize::BITS - rest.len().leading_zeros();
+        assert!(
+            carry_bits <= WORD_BITS,
+            "the carry of {} words doesn't fit in a word",
+            words.len()
+        );
+
+        let zero = self.load_constant(
+            layouter.namespace(|| self.region_name("no carry")),
+            F::zero(),
+        )?;
+        let (sum, carry) = rest.iter().enumerate().try_fold(
+            (first.clone(), zero),
+            |(sum, total), (i, word)| {
+                let (sum, carry) = self.add_checked(
+                    layouter.namespace(|| self.region_name(&format!("+ word {}", i + 1))),
+                    sum,
+                    word.clone(),
+                )?;
+                let total = self.add(
+                    layouter.namespace(|| self.region_name(&format!("carry {}", i + 1))),
+                    total,
+                    carry,
+                )?;
+                Ok::<_, Error>((sum, total))
+            },
+        )?;
+
+        let carry = self.zero_extend(
+            layouter.namespace(|| self.region_name("range check carry")),
+            carry,
+            carry_bits,
+        )?;
+        Ok((sum, carry))
+    }
+
+    fn saturating_add(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let (sum, carry) =
+            self.add_checked(layouter.namespace(|| self.region_name("a + b")), a, b)?;
+        let max = self.load_constant(
+            layouter.namespace(|| self.region_name("max")),
+            F::from_u128((1 << WORD_BITS) - 1),
+        )?;
+
+        // `add_checked` has already constrained the carry to be boolean.
+        self.mux(
+            layouter.namespace(|| self.region_name("clamp")),
+            carry,
+            sum,
+            max,
+        )
+    }
+
+    fn next_pow2(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let (a_minus_1, borrow) =
+            self.sub_const(layouter.namespace(|| self.region_name("a - 1")), a, 1)?;
+        let one = self.load_constant(layouter.namespace(|| self.region_name("1")), F::one())?;
+
+        // The bits come out of `decompose_bits` boolean, and so does every
+        // select over them, so `mux` can skip the check.
+        let bits = self.decompose_bits(
+            layouter.namespace(|| self.region_name("decompose a - 1")),
+            a_minus_1,
+        )?;
+        let mut smeared = bits.clone();
+        for i in (0..bits.len() - 1).rev() {
+            smeared[i] = self.mux(
+                layouter.namespace(|| self.region_name(&format!("smear bit {}", i))),
+                bits[i].clone(),
+                smeared[i + 1].clone(),
+                one.clone(),
+            )?;
+        }
+        let smeared = self.compose_bits(
+            layouter.namespace(|| self.region_name("compose smear")),
+            &smeared,
+        )?;
+
+        let (pow2, _) = self.add_checked(
+            layouter.namespace(|| self.region_name("smear + 1")),
+            smeared,
+            one.clone(),
+        )?;
+        self.mux(
+            layouter.namespace(|| self.region_name("a == 0")),
+            borrow,
+            pow2,
+            one,
+        )
+    }
+
+    fn sub_const(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        c: u64,
+    ) -> Result<(Self::Word, Self::Word), Error> {
+        assert!(
+            u128::from(c) < 1 << WORD_BITS,
+            "{} doesn't fit in {} bits",
+            c,
+            WORD_BITS
+        );
+
+        self.verify_decompose(
+            layouter.namespace(|| self.region_name("range check a")),
+            a.clone(),
+        )?;
+        let c = self.load_constant(
+            layouter.namespace(|| self.region_name(&format!("{}", c))),
+            F::from(c),
+        )?;
+        self.borrow(layouter.namespace(|| self.region_name("a - c")), a, c)
+    }
+
+    fn clear(&self, mut layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error> {
+        let not_a = self.not(layouter.namespace(|| self.region_name("not a")), a.clone())?;
+        self.and(
+            layouter.namespace(|| self.region_name("a & not a")),
+            a,
+            not_a,
+        )
+    }
+
+    fn neg(&self, mut layouter: impl Layouter<F>, a: Self::Word) -> Result<Self::Word, Error> {
+        let not_a = self.not(layouter.namespace(|| self.region_name("not a")), a)?;
+        let one = self.load_constant(layouter.namespace(|| self.region_name("one")), F::one())?;
+
+        // `not` has range-checked `not_a` already, and 1 is a constant, so
+        // only the sum needs checking. Only `neg(0)` carries, out of
+        // `0xFF..F + 1`, and wraps to 0.
+        let (sum, _) = self.add_carry(
+            layouter.namespace(|| self.region_name("not a + 1")),
+            not_a,
+            one,
+        )?;
+        Ok(sum)
+    }
+
+    fn lt(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        self.verify_decompose(
+            layouter.namespace(|| self.region_name("range check a")),
+            a.clone(),
+        )?;
+        self.verify_decompose(
+            layouter.namespace(|| self.region_name("range check b")),
+            b.clone(),
+        )?;
+        let (_, lt) = self.borrow(layouter.namespace(|| self.region_name("a < b")), a, b)?;
+        Ok(lt)
+    }
+
+    fn lt_signed(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let sign = F::from_u128(1 << (WORD_BITS - 1));
+
+        // `add_checked` range-checks both `a` and `b` and the flipped sums,
+        // which is all `borrow` needs. Without the operands' checks, `2^8 + 5`
+        // would flip to the same sum as 5.
+        let sign_a = self.load_constant(
+            layouter.namespace(|| self.region_name("sign bit for a")),
+            sign,
+        )?;
+        let (a, _) = self.add_checked(
+            layouter.namespace(|| self.region_name("flip sign of a")),
+            a,
+            sign_a,
+        )?;
+        let sign_b = self.load_constant(
+            layouter.namespace(|| self.region_name("sign bit for b")),
+            sign,
+        )?;
+        let (b, _) = self.add_checked(
+            layouter.namespace(|| self.region_name("flip sign of b")),
+            b,
+            sign_b,
+        )?;
+
+        let (_, lt) = self.borrow(layouter.namespace(|| self.region_name("a < b")), a, b)?;
+        Ok(lt)
+    }
+
+    fn minmax_signed(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<(Self::Word, Self::Word), Error> {
+        let b_lt_a = self.lt_signed(
+            layouter.namespace(|| self.region_name("b < a")),
+            b.clone(),
+            a.clone(),
+        )?;
+        self.cswap(
+            layouter.namespace(|| self.region_name("order")),
+            b_lt_a,
+            a,
+            b,
+        )
+    }
+
+    fn eq_const(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        c: u64,
+    ) -> Result<Self::Word, Error> {
+        let config = self.config();
+
+        layouter.assign_region(
+            || self.region_name("eq const"),
+            |mut region: Region<'_, F>| {
+                config.s_is_equal.enable(&mut region, 0)?;
+
+                a.0.copy_advice(|| "a", &mut region, config.advice[0], 0)?;
+                region.assign_advice_from_constant(|| "c", config.advice[1], 0, F::from(c))?;
+
+                let diff = a.0.value().map(|a| *a - F::from(c));
+                region.assign_advice(
+                    || "inv",
+                    config.advice[0],
+                    1,
+                    || {
+                        diff.map(|diff| diff.invert().unwrap_or(F::zero()))
+                            .ok_or(Error::Synthesis)
+                    },
+                )?;
+                region
+                    .assign_advice(
+                        || "a == c",
+                        config.advice[1],
+                        1,
+                        || {
+                            diff.map(|diff| F::from(bool::from(diff.is_zero()) as u64))
+                                .ok_or(Error::Synthesis)
+                        },
+                    )
+                    .map(Word)
+            },
+        )
+    }
+
+    fn disjoint(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<Self::Word, Error> {
+        let a_and_b = self.and(layouter.namespace(|| self.region_name("a & b")), a, b)?;
+        self.eq_const(
+            layouter.namespace(|| self.region_name("a & b == 0")),
+            a_and_b,
+            0,
+        )
+    }
+
+    fn assert_equal(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Self::Word,
+        b: Self::Word,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || self.region_name("assert equal"),
+            |mut region| region.constrain_equal(a.0.cell(), b.0.cell()),
+        )
+    }
+
+    fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        num: Self::Word,
+        column: usize,
+        row: usize,
+    ) -> Result<(), Error> {
+        let config = self.config();
+
+        let instance = *config.instance.get(column).ok_or(Error::Synthesis)?;
+        layouter.constrain_instance(num.0.cell(), instance, row)
+    }
+}
+
+/// The sums `ae + be` and `ao + bo` of the spread lanes of two words, split
+/// once more. Each 2-bit lane of a sum holds `a_i + b_i`, so its low bit is
+/// `a_i ^ b_i` and its high bit is `a_i & b_i`.
+struct SpreadSums<F: FieldExt> {
+    even_xor: Word<F>,
+    even_and: Word<F>,
+    odd_xor: Word<F>,
+    odd_and: Word<F>,
+}
+
+impl<F: FieldExt, const WORD_BITS: u32> AndChip<F, WORD_BITS> {
+    /// `load_private` for values that have to fit in `WORD_BITS` bits. A
+    /// known value that doesn't fails fast with `AndError::OutOfRange`,
+    /// rather than as a lookup failure in whichever instruction it reaches
+    /// first. The word is range-checked in-circuit with `verify_decompose`
+    /// as well, so the proof doesn't rest on the host-side check.
+    pub fn load_private_checked(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Option<F>,
+    ) -> Result<Word<F>, AndError> {
+        if let Some(value) = value {
+            if word_value::<F, WORD_BITS>(&value).is_none() {
+                return Err(AndError::out_of_range(&value, WORD_BITS));
+            }
+        }
+
+        let word = self.load_private(layouter.namespace(|| self.region_name("load")), value)?;
+        self.verify_decompose(
+            layouter.namespace(|| self.region_name("range check")),
+            word.clone(),
+        )?;
+        Ok(word)
+    }
+
+    /// Hands a word of this chip over to `wide`, a chip over at least as
+    /// many bits configured in the same circuit, as `wide.zero_extend` from
+    /// `WORD_BITS`.
+    ///
+    /// The value carries over unchanged; what the bridge adds is a range
+    /// check by the wide chip, so the word keeps its narrow bound on the
+    /// wide side without relying on where it came from. Both chips need
+    /// equality on the columns the word is copied between, which sharing
+    /// the advice columns gives. Panics unless `WIDE_BITS >= WORD_BITS`.
+    pub fn widen<const WIDE_BITS: u32>(
+        &self,
+        layouter: impl Layouter<F>,
+        wide: &AndChip<F, WIDE_BITS>,
+        a: Word<F>,
+    ) -> Result<Word<F>, Error> {
+        assert!(
+            WIDE_BITS >= WORD_BITS,
+            "can't widen a {}-bit word to {} bits",
+            WORD_BITS,
+            WIDE_BITS
+        );
+        wide.zero_extend(layouter, a, WORD_BITS)
+    }
+
+    /// Rearranges the bits of `a`: bit `i` of the result is bit `perm[i]` of
+    /// `a`. `perm` has to be a permutation of `0..WORD_BITS`, and anything
+    /// else is `AndError::InvalidPermutation`.
+    ///
+    /// The permutation is fixed into the circuit by the order the bits of
+    /// `decompose_bits` are fed to `compose_bits` in, so it costs no gates of
+    /// its own: `2 * WORD_BITS + 2` rows, the same as `rotl`, and growing
+    /// linearly with `WORD_BITS`.
+    pub fn permute_bits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Word<F>,
+        perm: &[usize],
+    ) -> Result<Word<F>, AndError> {
+        let mut seen = vec![false; WORD_BITS as usize];
+        let valid = perm.len() == seen.len()
+            && perm
+                .iter()
+                .all(|&i| i < seen.len() && !std::mem::replace(&mut seen[i], true));
+        if !valid {
+            return Err(AndError::InvalidPermutation(perm.to_vec()));
+        }
+
+        let bits = self.decompose_bits(layouter.namespace(|| self.region_name("decompose")), a)?;
+        let permuted: Vec<_> = perm.iter().map(|&i| bits[i].clone()).collect();
+        Ok(self.compose_bits(
+            layouter.namespace(|| self.region_name("compose permuted")),
+            &permuted,
+        )?)
+    }
+
+    /// Returns the AND of every run of `window` consecutive words, in order:
+    /// `words.len() - window + 1` of them. A `window` of 0 or longer than
+    /// `words` is `AndError::InvalidWindow`, and a `window` of 1 returns the
+    /// words themselves without assigning anything.
+    ///
+    /// Recomputing each window from scratch takes `window - 1` ANDs per
+    /// output. Instead, the words are cut into blocks of `window`, with an
+    /// `and` chain running forwards through each block and one
+    /// running backwards, and a window straddling two blocks is the AND of
+    /// its part in each. That is at most three ANDs, `3 * AND_ROWS` rows,
+    /// per output whatever the window, which is ahead from a window of 5 up.
+    pub fn windowed_and(
+        &self,
+        mut layouter: impl Layouter<F>,
+        words: &[Word<F>],
+        window: usize,
+    ) -> Result<Vec<Word<F>>, AndError> {
+        let len = words.len();
+        if window == 0 || window > len {
+            return Err(AndError::InvalidWindow { window, len });
+        }
+        if window == 1 {
+            return Ok(words.to_vec());
+        }
+
+        // `prefix[j]` is the AND of `words` from the start of `j`'s block
+        // through `j`.
+        let mut prefix: Vec<Word<F>> = Vec::with_capacity(len);
+        for (j, word) in words.iter().enumerate() {
+            prefix.push(if j % window == 0 {
+                word.clone()
+            } else {
+                self.and(
+                    layouter.namespace(|| self.region_name(&format!("prefix {}", j))),
+                    prefix[j - 1].clone(),
+                    word.clone(),
+                )?
+            });
+        }
+
+        // `suffix[i]` is the AND of `words` from `i` through the end of its
+        // block, needed only for windows starting mid-block.
+        let starts = len - window + 1;
+        let end = std::cmp::min(len, (starts - 1) / window * window + window);
+        let mut suffix: Vec<Option<Word<F>>> = vec![None; end];
+        for i in (0..end).rev().filter(|i| i % window != 0) {
+            suffix[i] = Some(match suffix.get(i + 1).cloned().flatten() {
+                Some(rest) => self.and(
+                    layouter.namespace(|| self.region_name(&format!("suffix {}", i))),
+                    rest,
+                    words[i].clone(),
+                )?,
+                _ => words[i].clone(),
+            });
+        }
+
+        (0..starts)
+            .map(|i| -> Result<_, AndError> {
+                let last = prefix[i + window - 1].clone();
+                match suffix[i].clone() {
+                    Some(first) => Ok(self.and(
+                        layouter.namespace(|| self.region_name(&format!("window {}", i))),
+                        first,
+                        last,
+                    )?),
+                    None => Ok(last),
+                }
+            })
+            .collect()
+    }
+
+    /// `a & b` the obvious way, as a baseline for `and`'s spread lanes: both
+    /// words are decomposed into bits, the bits multiplied pairwise by a mux
+    /// against zero, and the products recomposed.
+    ///
+    /// That is `5 * WORD_BITS + 4` rows, 44 at 8 bits against `and`'s
+    /// `AND_ROWS` of 14, and the gap widens with `WORD_BITS`. In exchange it
+    /// needs no table. The result is the same word `and` gives.
+    pub fn naive_and(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Word<F>,
+        b: Word<F>,
+    ) -> Result<Word<F>, Error> {
+        let a_bits =
+            self.decompose_bits(layouter.namespace(|| self.region_name("decompose a")), a)?;
+        let b_bits =
+            self.decompose_bits(layouter.namespace(|| self.region_name("decompose b")), b)?;
+        let zero =
+            self.load_constant(layouter.namespace(|| self.region_name("zero")), F::zero())?;
+
+        // `decompose_bits` has already constrained the bits to be boolean,
+        // and `mux(a_i, 0, b_i)` is `a_i * b_i`.
+        let products = a_bits
+            .into_iter()
+            .zip(b_bits)
+            .enumerate()
+            .map(|(i, (a, b))| {
+                self.mux(
+                    layouter.namespace(|| self.region_name(&format!("bit {}", i))),
+                    a,
+                    zero.clone(),
+                    b,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.compose_bits(
+            layouter.namespace(|| self.region_name("compose")),
+            &products,
+        )
+    }
+
+    /// Returns `a - b` modulo `2^WORD_BITS`, and 1 if `a < b` and 0
+    /// otherwise, for words the caller has already range-checked.
+    ///
+    /// The `add carry` gate is laid out as `b + diff = a + lt * 2^WORD_BITS`.
+    /// With `diff` range-checked as well, `lt = 0` only fits `a >= b` and
+    /// `lt = 1` only fits `a < b`.
+    fn borrow(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Word<F>,
+        b: Word<F>,
+    ) -> Result<(Word<F>, Word<F>), Error> {
+        let config = self.config();
+
+        let (diff, lt) = layouter.assign_region(
+            || self.region_name("lt"),
+            |mut region: Region<'_, F>| {
+                config.s_add_carry.enable(&mut region, 0)?;
+
+                b.0.copy_advice(|| "b", &mut region, config.advice[0], 0)?;
+                a.0.copy_advice(|| "a", &mut region, config.advice[0], 1)?;
+
+                let values =
+                    a.0.value()
+                        .zip(b.0.value())
+                        .map(|(a, b)| (a.get_lower_128(), b.get_lower_128()));
+                let diff = region.assign_advice(
+                    || "diff",
+                    config.advice[1],
+                    0,
+                    || {
+                        values
+                            .map(|(a, b)| F::from_u128(a.wrapping_sub(b) % (1 << WORD_BITS)))
+                            .ok_or(Error::Synthesis)
+                    },
+                )?;
+                let lt = region.assign_advice(
+                    || "a < b",
+                    config.advice[1],
+                    1,
+                    || {
+                        values
+                            .map(|(a, b)| F::from((a < b) as u64))
+                            .ok_or(Error::Synthesis)
+                    },
+                )?;
+                Ok((Word(diff), Word(lt)))
+            },
+        )?;
+
+        self.verify_decompose(
+            layouter.namespace(|| self.region_name("range check diff")),
+            diff.clone(),
+        )?;
+        Ok((diff, lt))
+    }
+
+    /// Constrains the low bit of `a` to be `bit`, for `assert_even` and
+    /// `assert_odd`.
+    fn assert_low_bit(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Word<F>,
+        bit: bool,
+    ) -> Result<(), Error> {
+        let bits = self.decompose_bits(layouter.namespace(|| self.region_name("decompose")), a)?;
+        let expected = self.load_constant(
+            layouter.namespace(|| self.region_name(&format!("low bit {}", bit as u8))),
+            F::from(bit as u64),
+        )?;
+        self.assert_equal(
+            layouter.namespace(|| self.region_name("low bit")),
+            bits[0].clone(),
+            expected,
+        )
+    }
+
+    /// `select` without the boolean check on `cond`, for callers that have
+    /// already asserted it.
+    fn mux(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cond: Word<F>,
+        a: Word<F>,
+        b: Word<F>,
+    ) -> Result<Word<F>, Error> {
+        let config = self.config();
+
+        layouter.assign_region(
+            || self.region_name("select"),
+            |mut region: Region<'_, F>| {
+                config.s_select.enable(&mut region, 0)?;
+
+                cond.0
+                    .copy_advice(|| "cond", &mut region, config.advice[0], 0)?;
+                a.0.copy_advice(|| "a", &mut region, config.advice[1], 0)?;
+                b.0.copy_advice(|| "b", &mut region, config.advice[0], 1)?;
+
+                let value = cond
+                    .0
+                    .value()
+                    .zip(a.0.value())
+                    .zip(b.0.value())
+                    .map(|((cond, a), b)| *a + *cond * (*b - *a));
+                region
+                    .assign_advice(
+                        || "out",
+                        config.advice[1],
+                        1,
+                        || value.ok_or(Error::Synthesis),
+                    )
+                    .map(Word)
+            },
+        )
+    }
+
+    /// `a + b` through the `add carry` gate, split at bit `WORD_BITS` into
+    /// a range-checked sum and a boolean carry. The operands are taken as
+    /// they are: it is up to the caller to know they are in range.
+    fn add_carry(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Word<F>,
+        b: Word<F>,
+    ) -> Result<(Word<F>, Word<F>), Error> {
+        let config = self.config();
+
+        let (sum, carry) = layouter.assign_region(
+            || self.region_name("add carry"),
+            |mut region: Region<'_, F>| {
+                config.s_add_carry.enable(&mut region, 0)?;
+
+                a.0.copy_advice(|| "lhs", &mut region, config.advice[0], 0)?;
+                b.0.copy_advice(|| "rhs", &mut region, config.advice[1], 0)?;
+
+                let total =
+                    a.0.value()
+                        .zip(b.0.value())
+                        .map(|(a, b)| a.get_lower_128() + b.get_lower_128());
+                let sum = region.assign_advice(
+                    || "sum",
+                    config.advice[0],
+                    1,
+                    || {
+                        total
+                            .map(|t| F::from_u128(t % (1 << WORD_BITS)))
+                            .ok_or(Error::Synthesis)
+                    },
+                )?;
+                let carry = region.assign_advice(
+                    || "carry",
+                    config.advice[1],
+                    1,
+                    || {
+                        total
+                            .map(|t| F::from_u128(t >> WORD_BITS))
+                            .ok_or(Error::Synthesis)
+                    },
+                )?;
+                Ok((Word(sum), Word(carry)))
+            },
+        )?;
+
+        // The gate only pins down `sum + carry * 2^WORD_BITS`. Range-checking
+        // the sum is what leaves the honest carry as the only option.
+        self.verify_decompose(
+            layouter.namespace(|| self.region_name("range check sum")),
+            sum.clone(),
+        )?;
+        Ok((sum, carry))
+    }
+
+    /// Looks the AND of two spread lanes up in the `DirectLookup` table. The
+    /// lanes need to come out of `verify_decompose`; the lookup itself then
+    /// pins the result to a spread lane as well.
+    fn lane_and(
+        &self,
+        mut layouter: impl Layouter<F>,
+        and_lookup: AndLookup,
+        x: Word<F>,
+        y: Word<F>,
+    ) -> Result<Word<F>, Error> {
+        let config = self.config();
+
+        layouter.assign_region(
+            || self.region_name("and lookup"),
+            |mut region: Region<'_, F>| {
+                and_lookup.s_and.enable(&mut region, 0)?;
+
+                x.0.copy_advice(|| "x", &mut region, config.advice[0], 0)?;
+                y.0.copy_advice(|| "y", &mut region, config.advice[1], 0)?;
+
+                // ANDing two spread lanes leaves the zeros between their bits
+                // alone, so it is the spread of the AND of the lanes.
+                let value =
+                    x.0.value()
+                        .zip(y.0.value())
+                        .map(|(x, y)| F::from_u128(x.get_lower_128() & y.get_lower_128()));
+                region
+                    .assign_advice(
+                        || "x & y",
+                        config.advice[0],
+                        1,
+                        || value.ok_or(Error::Synthesis),
+                    )
+                    .map(Word)
+            },
+        )
+    }
+
+    fn spread_sums(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Word<F>,
+        b: Word<F>,
+    ) -> Result<SpreadSums<F>, Error> {
+        let (ae, ao) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("a decomposition")),
+            a,
+        )?;
+        let (be, bo) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("b decomposition")),
+            b,
+        )?;
+
+        let e = self.add(layouter.namespace(|| self.region_name("ae + be")), ae, be)?;
+        let o = self.add(layouter.namespace(|| self.region_name("ao + bo")), ao, bo)?;
+
+        let (even_xor, even_and) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("e decomposition")),
+            e,
+        )?;
+        let (odd_xor, odd_and) = self.verify_decompose(
+            layouter.namespace(|| self.region_name("o decomposition")),
+            o,
+        )?;
+
+        Ok(SpreadSums {
+            even_xor,
+            even_and,
+            odd_xor,
+            odd_and,
+        })
+    }
+}
+
+/// The full circuit implementation.
+///
+/// In this struct we store the private input variables. We use `Option<F>` because
+/// they won't have any value during key generation. During proving, if any of these
+/// were `None` we would get an error.
+#[derive(Default)]
+pub struct MyCircuit<F: FieldExt, const WORD_BITS: u32 = 8> {
+    pub a: Option<F>,
+    pub b: Option<F>,
+    /// The row of the instance column `a & b` is exposed at. The public
+    /// inputs are a single vector with `a & b` at this index; the rows above
+    /// it are left to other chips and are not constrained by this circuit,
+    /// so the vector must be at least `output_row + 1` long.
+    pub output_row: usize,
+}
+
+impl<F: FieldExt, const WORD_BITS: u32> Circuit<F> for MyCircuit<F, WORD_BITS> {
+    // Since we are using a single chip for everything, we can just reuse its config.
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            output_row: self.output_row,
+            ..Self::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        // We create the two advice columns that FieldChip uses for I/O.
+        let advice = [meta.advice_column(), meta.advice_column()];
+
+        // We also need an instance column to store public inputs.
+        let instance = vec![meta.instance_column()];
+
+        // Create a fixed column to load constants.
+        let constant = meta.fixed_column();
+
+        AndChip::<F, WORD_BITS>::configure(meta, advice, instance, constant)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let field_chip = AndChip::<F, WORD_BITS>::construct(config);
+        field_chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        // Load our private values into the circuit.
+        let a = field_chip.load_private(layouter.namespace(|| "load a"), self.a)?;
+        let b = field_chip.load_private(layouter.namespace(|| "load b"), self.b)?;
+
+        let a_and_b = field_chip.and(layouter.namespace(|| "a & b"), a, b)?;
+
+        // Expose the result as a public input to the circuit.
+        field_chip.expose_public(
+            layouter.namespace(|| "expose a_and_b"),
+            a_and_b,
+            0,
+            self.output_row,
+        )
+    }
+}
+
+impl<F: FieldExt, const WORD_BITS: u32> MyCircuit<F, WORD_BITS> {
+    /// The smallest `k` this circuit fits in. That is `min_k`, unless
+    /// `output_row` reaches past the rows it leaves usable:
+    ///
+    /// `2^k - UNUSABLE_ROWS >= max(2 + AND_ROWS, 2^(WORD_BITS / 2), output_row + 1)`
+    pub fn required_k(&self) -> u32 {
+        let rows = std::cmp::max(
+            std::cmp::max(2 + AND_ROWS, 1 << (WORD_BITS / 2)),
+            self.output_row + 1,
+        ) + UNUSABLE_ROWS;
+        usize::BITS - (rows - 1).leading_zeros()
+    }
+
+    /// `configure`, with the chip's `and` implemented by `backend`.
+    pub fn configure_with_backend(
+        meta: &mut ConstraintSystem<F>,
+        backend: TableBackend,
+    ) -> AndConfig {
+        Self::configure_with_tables(meta, backend, SpreadTable::Full)
+    }
+
+    /// `configure`, with the chip's `and` implemented by `backend` and its
+    /// `even_bits` table laid out as `spread_table`.
+    pub fn configure_with_tables(
+        meta: &mut ConstraintSystem<F>,
+        backend: TableBackend,
+        spread_table: SpreadTable,
+    ) -> AndConfig {
+        let advice = [meta.advice_column(), meta.advice_column()];
+        let instance = vec![meta.instance_column()];
+        let constant = meta.fixed_column();
+
+        AndChip::<F, WORD_BITS>::configure_with_tables(
+            meta,
+            advice,
+            instance,
+            constant,
+            backend,
+            spread_table,
+        )
+    }
+}
+
+/// `MyCircuit` with its lanes range-checked against a
+/// `SpreadTable::Columns(COLUMNS)` table, `2^(WORD_BITS/(2 * COLUMNS))` rows
+/// over `COLUMNS` table columns. At 16 bits and 2 columns that brings a
+/// single AND down from `k = 9` to `k = 5`.
+#[derive(Default)]
+pub struct SplitTableCircuit<const WORD_BITS: u32 = 16, const COLUMNS: u32 = 2>(
+    pub MyCircuit<Fp, WORD_BITS>,
+);
+
+impl<const WORD_BITS: u32, const COLUMNS: u32> Circuit<Fp>
+    for SplitTableCircuit<WORD_BITS, COLUMNS>
+{
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MyCircuit::<Fp, WORD_BITS>::configure_with_tables(
+            meta,
+            TableBackend::Spread,
+            SpreadTable::Columns(COLUMNS),
+        )
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+/// The smallest `k` that `MockProver` lays `circuit` out in.
+#[cfg(test)]
+fn smallest_k<C: Circuit<Fp>>(circuit: &C, instance: Vec<Vec<Fp>>) -> u32 {
+    (1..)
+        .find(|k| MockProver::run(*k, circuit, instance.clone()).is_ok())
+        .unwrap()
+}
+
+#[test]
+fn split_table_test() {
+    let circuit = |a: u64, b: u64| MyCircuit::<Fp, 16> {
+        a: Some(Fp::from(a)),
+        b: Some(Fp::from(b)),
+        output_row: 0,
+    };
+
+    // The full table is 256 rows; split over 2 columns it is 16, and the
+    // AND's extra 8 rows of sub-lanes still fit in `k = 5`.
+    let instance = vec![vec![Fp::zero()]];
+    assert_eq!(smallest_k(&circuit(0, 0), instance.clone()), 9);
+    assert_eq!(circuit(0, 0).required_k(), 9);
+    let k = smallest_k(&SplitTableCircuit::<16, 2>(circuit(0, 0)), instance);
+    assert_eq!(k, 5);
+
+    for (a, b) in [
+        (0, 0),
+        (0xFFFF, 0xFFFF),
+        (0xA5A5, 0x3C3C),
+        (0x8000, 0x8001),
+        (0x00FF, 0xFF00),
+        (0x1234, 0xFEDC),
+    ] {
+        // The same AND as the full table proves, at the lower `k`.
+        let full = MockProver::run(9, &circuit(a, b), vec![vec![Fp::from(a & b)]]).unwrap();
+        assert_eq!(full.verify(), Ok(()), "{:#06x} & {:#06x}", a, b);
+
+        let split = SplitTableCircuit::<16, 2>(circuit(a, b));
+        let prover = MockProver::run(k, &split, vec![vec![Fp::from(a & b)]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()), "{:#06x} & {:#06x}", a, b);
+
+        let prover = MockProver::run(k, &split, vec![vec![Fp::from((a & b) ^ 1)]]).unwrap();
+        assert!(prover.verify().is_err(), "{:#06x} & {:#06x}", a, b);
+    }
+
+    // A word past 16 bits has a lane past 8 bits, whose sub-lanes can't add
+    // up to it.
+    let prover = MockProver::run(
+        k,
+        &SplitTableCircuit::<16, 2>(circuit(0x1_0000, 0x1_0000)),
+        vec![vec![Fp::from(0x1_0000)]],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+
+    // Four columns of 2-bit sub-lanes work the same way.
+    let split = SplitTableCircuit::<16, 4>(circuit(0xA5A5, 0x3C3C));
+    let prover = MockProver::run(6, &split, vec![vec![Fp::from(0xA5A5 & 0x3C3C)]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+/// `MyCircuit` with its AND looked up in the `TableBackend::DirectLookup`
+/// table. The table takes `2^WORD_BITS` rows, so this needs `k > WORD_BITS`.
+#[derive(Default)]
+pub struct LookupAndCircuit<const WORD_BITS: u32 = 8>(pub MyCircuit<Fp, WORD_BITS>);
+
+impl<const WORD_BITS: u32> Circuit<Fp> for LookupAndCircuit<WORD_BITS> {
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MyCircuit::<Fp, WORD_BITS>::configure_with_backend(meta, TableBackend::DirectLookup)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fp>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+#[cfg(test)]
+fn table_backends_agree<const WORD_BITS: u32>(a: u64, b: u64) {
+    let circuit = || MyCircuit::<Fp, WORD_BITS> {
+        a: Some(Fp::from(a)),
+        b: Some(Fp::from(b)),
+        output_row: 0,
+    };
+    let k = WORD_BITS + 1;
+    let expected = vec![vec![Fp::from(a & b)]];
+
+    let prover = MockProver::run(k, &circuit(), expected.clone()).unwrap();
+    assert_eq!(prover.verify(), Ok(()), "spread {} & {}", a, b);
+    let prover = MockProver::run(k, &LookupAndCircuit(circuit()), expected).unwrap();
+    assert_eq!(prover.verify(), Ok(()), "direct lookup {} & {}", a, b);
+
+    let prover = MockProver::run(
+        k,
+        &LookupAndCircuit(circuit()),
+        vec![vec![Fp::from((a & b) ^ 1)]],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err(), "direct lookup {} & {}", a, b);
+}
+
+#[test]
+fn table_backends_test() {
+    for a in 0..16 {
+        for b in 0..16 {
+            table_backends_agree::<4>(a, b);
+        }
+    }
+    for a in (0..256).step_by(17) {
+        for b in (0..256).step_by(13) {
+            table_backends_agree::<8>(a, b);
+        }
+    }
+}
+
+// Every 8-bit pair, checked host-side: the `DirectLookup` table's entry for
+// each pair of lanes against the AND lanes the spread backend reads off their
+// sum. `table_backends_test` runs both circuits on a sample of the pairs.
+#[test]
+fn table_backends_exhaustive_test() {
+    let table: HashMap<_, _> = and_table_rows::<8>()
+        .map(|[x, y, out]| ((x, y), out))
+        .collect();
+    let lane = |x: Fp| x.get_lower_128() as u64;
+
+    for a in 0..256u64 {
+        for b in 0..256u64 {
+            let (ae, ao) = decompose(Fp::from(a));
+            let (be, bo) = decompose(Fp::from(b));
+            let direct = [(ae, be), (ao, bo)].map(|(x, y)| table[&(lane(x), lane(y))]);
+            let spread = [ae + be, ao + bo].map(|sum| lane(decompose(sum).1));
+            assert_eq!(direct, spread, "{} & {}", a, b);
+            assert_eq!(direct[0] + 2 * direct[1], a & b, "{} & {}", a, b);
+        }
+    }
+}
+
+#[test]
+fn table_backend_rows_test() {
+    let circuit = || MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(0xA5)),
+        b: Some(Fp::from(0x3C)),
+        output_row: 0,
+    };
+    let spread = SynthesisTrace::of(&circuit()).unwrap();
+    let direct = SynthesisTrace::of(&LookupAndCircuit(circuit())).unwrap();
+
+    // Two decompositions, two lookups and a compose, against the spread
+    // backend's four decompositions, two adds and a compose.
+    assert_eq!(spread.advice_rows - direct.advice_rows, AND_ROWS - 10);
+    assert_eq!(direct.region_histogram().get("and lookup"), Some(&2));
+    assert_eq!(direct.region_histogram().get("add"), None);
+}
+
+#[test]
+fn output_row_test() {
+    let circuit = MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(0xA5)),
+        b: Some(Fp::from(0x3C)),
+        output_row: 3,
+    };
+
+    // Rows 0 to 2 belong to other chips, so any values verify there.
+    let public_inputs = vec![
+        Fp::from(7),
+        Fp::from(11),
+        Fp::from(13),
+        Fp::from(0xA5 & 0x3C),
+    ];
+    let prover = MockProver::run(circuit.required_k(), &circuit, vec![public_inputs]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let public_inputs = vec![Fp::from(0xA5 & 0x3C), Fp::zero(), Fp::zero(), Fp::zero()];
+    let prover = MockProver::run(circuit.required_k(), &circuit, vec![public_inputs]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+/// Runs `circuit` under the floor planner `P` instead of its own.
+///
+/// `SimpleFloorPlanner` stacks regions in the order they are assigned, each
+/// one below the last row any of its columns is used at. `V1` measures all
+/// regions first and packs them, which pays off once a circuit has regions
+/// over disjoint columns that can sit side by side, like the Poseidon
+/// regions next to the AND ones in `CommittedAndCircuit`. Every `AndChip`
+/// region uses `advice[0]`, so on its own the chip lays out the same either
+/// way.
+pub struct WithFloorPlanner<C, P> {
+    pub circuit: C,
+    _planner: PhantomData<P>,
+}
+
+impl<C, P> WithFloorPlanner<C, P> {
+    pub fn new(circuit: C) -> Self {
+        Self {
+            circuit,
+            _planner: PhantomData,
+        }
+    }
+}
+
+impl<F: Field, C: Circuit<F>, P: FloorPlanner> Circuit<F> for WithFloorPlanner<C, P> {
+    type Config = C::Config;
+    type FloorPlanner = P;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.circuit.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.circuit.synthesize(config, layouter)
+    }
+}
+
+#[test]
+fn floor_planner_test() {
+    use halo2_proofs::circuit::floor_planner::V1;
+
+    let circuit = || MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(3)),
+        b: Some(Fp::from(4)),
+        output_row: 0,
+    };
+    let simple = WithFloorPlanner::<_, SimpleFloorPlanner>::new(circuit());
+    let v1 = WithFloorPlanner::<_, V1>::new(circuit());
+
+    let public_inputs = vec![Fp::from(3 & 4)];
+    let prover = MockProver::run(5, &simple, vec![public_inputs.clone()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(5, &v1, vec![public_inputs]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let simple_rows = SynthesisTrace::of(&simple).unwrap().advice_rows;
+    let v1_rows = SynthesisTrace::of(&v1).unwrap().advice_rows;
+    assert!(v1_rows <= simple_rows, "{} > {}", v1_rows, simple_rows);
+}
+
+/// Proves `a1 & b1 == a2 & b2` without revealing either side: the two AND
+/// results are only ever constrained equal to each other, and nothing is
+/// exposed.
+#[derive(Default)]
+pub struct CircuitEqAnd<const WORD_BITS: u32 = 8> {
+    pub a1: Option<Fp>,
+    pub b1: Option<Fp>,
+    pub a2: Option<Fp>,
+    pub b2: Option<Fp>,
+}
+
+impl<const WORD_BITS: u32> Circuit<Fp> for CircuitEqAnd<WORD_BITS> {
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MyCircuit::<Fp, WORD_BITS>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let field_chip = AndChip::<Fp, WORD_BITS>::construct(config);
+        field_chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        let a1 = field_chip.load_private(layouter.namespace(|| "load a1"), self.a1)?;
+        let b1 = field_chip.load_private(layouter.namespace(|| "load b1"), self.b1)?;
+        let a2 = field_chip.load_private(layouter.namespace(|| "load a2"), self.a2)?;
+        let b2 = field_chip.load_private(layouter.namespace(|| "load b2"), self.b2)?;
+
+        let lhs = field_chip.and(layouter.namespace(|| "a1 & b1"), a1, b1)?;
+        let rhs = field_chip.and(layouter.namespace(|| "a2 & b2"), a2, b2)?;
+
+        field_chip.assert_equal(layouter.namespace(|| "a1 & b1 == a2 & b2"), lhs, rhs)
+    }
+}
+
+#[test]
+fn circuit_eq_and_test() {
+    let circuit = |a1: u64, b1: u64, a2: u64, b2: u64| CircuitEqAnd::<8> {
+        a1: Some(Fp::from(a1)),
+        b1: Some(Fp::from(b1)),
+        a2: Some(Fp::from(a2)),
+        b2: Some(Fp::from(b2)),
+    };
+    let k = min_k_for_vec::<8>(2);
+
+    // 0b1100 & 0b1010 == 0b1001 & 0b1110
+    let prover =
+        MockProver::run(k, &circuit(0b1100, 0b1010, 0b1001, 0b1110), vec![vec![]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let prover = MockProver::run(k, &circuit(3, 3, 1, 1), vec![vec![]]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+/// Proves the AND of any number of private operands, exposed at instance
+/// row 0. `MyCircuit` is the two-operand case.
+///
+/// The operands are folded with `and_all`. `n` operands take `n` rows to
+/// load and `n - 1` ANDs at `AND_ROWS` each, which is under what
+/// `min_k_for_vec(n - 1)` budgets for, so `k` grows with `log2(n)`: one more
+/// for every doubling of the operands, once they outgrow the table.
+#[derive(Default)]
+pub struct MultiAndCircuit<const WORD_BITS: u32 = 8> {
+    pub operands: Vec<Option<Fp>>,
+}
+
+impl<const WORD_BITS: u32> MultiAndCircuit<WORD_BITS> {
+    /// The smallest `k` this circuit fits in, from `min_k_for_vec`.
+    pub fn required_k(&self) -> u32 {
+        min_k_for_vec::<WORD_BITS>(std::cmp::max(self.operands.len(), 2) - 1)
+    }
+}
+
+impl<const WORD_BITS: u32> Circuit<Fp> for MultiAndCircuit<WORD_BITS> {
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            operands: vec![None; self.operands.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MyCircuit::<Fp, WORD_BITS>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let field_chip = AndChip::<Fp, WORD_BITS>::construct(config);
+        field_chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        let operands = self
+            .operands
+            .iter()
+            .enumerate()
+            .map(|(i, operand)| {
+                field_chip.load_private(
+                    layouter.namespace(|| format!("load operand {}", i)),
+                    *operand,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let and = field_chip.and_all(layouter.namespace(|| "and all"), &operands)?;
+
+        field_chip.expose_public(layouter.namespace(|| "expose and"), and, 0, 0)
+    }
+}
+
+#[test]
+fn multi_and_circuit_test() {
+    for operands in [vec![0xFF, 0x3C, 0xA5], vec![0xFF, 0xFE, 0xFC, 0xF8, 0x7F]] {
+        let circuit = MultiAndCircuit::<8> {
+            operands: operands.iter().map(|x| Some(Fp::from(*x))).collect(),
+        };
+        let and = operands.iter().fold(0xFF, |acc, x| acc & x);
+        let k = circuit.required_k();
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(and)]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()), "{:x?}", operands);
+
+        // Leaving out the last operand changes the result.
+        let partial = operands[..operands.len() - 1]
+            .iter()
+            .fold(0xFF, |acc, x| acc & x);
+        assert_ne!(partial, and);
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(partial)]]).unwrap();
+        assert!(prover.verify().is_err(), "{:x?}", operands);
+    }
+
+    // Two operands lay out the same AND as `MyCircuit`.
+    let circuit = MultiAndCircuit::<8> {
+        operands: vec![Some(Fp::from(3)), Some(Fp::from(6))],
+    };
+    assert_eq!(circuit.required_k(), min_k::<8>());
+    assert_eq!(
+        SynthesisTrace::of(&circuit).unwrap().advice_rows,
+        SynthesisTrace::of(&MyCircuit::<Fp, 8> {
+            a: Some(Fp::from(3)),
+            b: Some(Fp::from(6)),
+            output_row: 0,
+        })
+        .unwrap()
+        .advice_rows
+    );
+}
+
+/// Proves a property of `a & b` without revealing `a & b` itself: only
+/// whether it is even is exposed, as a boolean at instance row 0.
+///
+/// The AND result stays in private cells; its low bit is extracted with
+/// `bits` and compared against 0 with `eq_const`.
+#[derive(Default)]
+pub struct PrivateAndCircuit<const WORD_BITS: u32 = 8> {
+    pub a: Option<Fp>,
+    pub b: Option<Fp>,
+}
+
+impl<const WORD_BITS: u32> Circuit<Fp> for PrivateAndCircuit<WORD_BITS> {
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MyCircuit::<Fp, WORD_BITS>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let field_chip = AndChip::<Fp, WORD_BITS>::construct(config);
+        field_chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        let a = field_chip.load_private(layouter.namespace(|| "load a"), self.a)?;
+        let b = field_chip.load_private(layouter.namespace(|| "load b"), self.b)?;
+        let a_and_b = field_chip.and(layouter.namespace(|| "a & b"), a, b)?;
+
+        let low_bit = field_chip.bits(layouter.namespace(|| "low bit"), a_and_b, 0, 1)?;
+        let is_even = field_chip.eq_const(layouter.namespace(|| "is even"), low_bit, 0)?;
+
+        field_chip.expose_public(layouter.namespace(|| "expose is_even"), is_even, 0, 0)
+    }
+}
+
+#[test]
+fn private_and_circuit_test() {
+    let circuit = |a: u64, b: u64| PrivateAndCircuit::<8> {
+        a: Some(Fp::from(a)),
+        b: Some(Fp::from(b)),
+    };
+    let k = 7;
+
+    // 6 & 4 = 4 is even.
+    let prover = MockProver::run(k, &circuit(6, 4), vec![vec![Fp::one()]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(k, &circuit(6, 4), vec![vec![Fp::zero()]]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // 7 & 5 = 5 is odd.
+    let prover = MockProver::run(k, &circuit(7, 5), vec![vec![Fp::zero()]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // Only the predicate is public.
+    assert_eq!(
+        SynthesisTrace::of(&circuit(6, 4))
+            .unwrap()
+            .public_input_count(),
+        1
+    );
+}
+
+/// Exposes `a & b` in one instance column and the carry out of `a + b` in a
+/// second one, so the prover takes `vec![vec![a & b], vec![carry]]`.
+#[cfg(test)]
+#[derive(Default)]
+struct TwoColumnCircuit<const WORD_BITS: u32 = 8> {
+    a: Option<Fp>,
+    b: Option<Fp>,
+}
+
+#[cfg(test)]
+impl<const WORD_BITS: u32> Circuit<Fp> for TwoColumnCircuit<WORD_BITS> {
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column()];
+        let instance = vec![meta.instance_column(), meta.instance_column()];
+        let constant = meta.fixed_column();
+
+        AndChip::<Fp, WORD_BITS>::configure(meta, advice, instance, constant)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let field_chip = AndChip::<Fp, WORD_BITS>::construct(config);
+        field_chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        let a = field_chip.load_private(layouter.namespace(|| "load a"), self.a)?;
+        let b = field_chip.load_private(layouter.namespace(|| "load b"), self.b)?;
+
+        let a_and_b = field_chip.and(layouter.namespace(|| "a & b"), a.clone(), b.clone())?;
+        let (_, carry) = field_chip.add_checked(layouter.namespace(|| "a + b"), a, b)?;
+
+        field_chip.expose_public(layouter.namespace(|| "expose a_and_b"), a_and_b, 0, 0)?;
+        field_chip.expose_public(layouter.namespace(|| "expose carry"), carry, 1, 0)
+    }
+}
+
+#[test]
+fn two_instance_columns_test() {
+    let circuit = TwoColumnCircuit::<8> {
+        a: Some(Fp::from(200)),
+        b: Some(Fp::from(100)),
+    };
+
+    let public_inputs = vec![vec![Fp::from(200 & 100)], vec![Fp::one()]];
+    let prover = MockProver::run(6, &circuit, public_inputs).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // Swapping the columns puts each value under the wrong constraint.
+    let public_inputs = vec![vec![Fp::one()], vec![Fp::from(200 & 100)]];
+    let prover = MockProver::run(6, &circuit, public_inputs).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn expose_public_column_out_of_range_test() {
+    let circuit = |column| {
+        chip_call(move |chip, layouter| {
+            let word = chip.load_private(layouter.namespace(|| "load"), Some(Fp::from(7)))?;
+            chip.expose_public(layouter.namespace(|| "expose"), word, column, 0)
+        })
+    };
+    assert!(SynthesisTrace::of(&circuit(0)).is_ok());
+    // `MyCircuit`'s columns have a single instance column.
+    assert!(matches!(
+        SynthesisTrace::of(&circuit(1)),
+        Err(Error::Synthesis)
+    ));
+}
+
+#[test]
+fn load_public_column_out_of_range_test() {
+    let circuit = |column| {
+        chip_call(move |chip, layouter| {
+            chip.load_public(layouter.namespace(|| "load"), column, 0)
+                .map(|_| ())
+        })
+    };
+    assert!(SynthesisTrace::of(&circuit(0)).is_ok());
+    assert!(matches!(
+        SynthesisTrace::of(&circuit(1)),
+        Err(Error::Synthesis)
+    ));
+}
+
+/// Proves `c[i] = a[i] & b[i]` for public vectors of `len` words each.
+///
+/// There are three instance columns, each one a vector: `a` in column 0, `b`
+/// in column 1 and `c` in column 2, element `i` at row `i`. The prover and
+/// verifier take `vec![a, b, c]`. Nothing is private, so the circuit holds
+/// no witnesses; the operands are read from the instance columns and
+/// range-checked by the ANDs.
+#[derive(Default)]
+pub struct PublicVecAndCircuit<const WORD_BITS: u32 = 8> {
+    pub len: usize,
+}
+
+impl<const WORD_BITS: u32> Circuit<Fp> for PublicVecAndCircuit<WORD_BITS> {
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { len: self.len }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column()];
+        let instance = vec![
+            meta.instance_column(),
+            meta.instance_column(),
+            meta.instance_column(),
+        ];
+        let constant = meta.fixed_column();
+
+        AndChip::<Fp, WORD_BITS>::configure(meta, advice, instance, constant)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let field_chip = AndChip::<Fp, WORD_BITS>::construct(config);
+        field_chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        for i in 0..self.len {
+            let a =
+                field_chip.load_public(layouter.namespace(|| format!("load a[{}]", i)), 0, i)?;
+            let b =
+                field_chip.load_public(layouter.namespace(|| format!("load b[{}]", i)), 1, i)?;
+            let c = field_chip.and(layouter.namespace(|| format!("a[{}] & b[{}]", i, i)), a, b)?;
+            field_chip.expose_public(layouter.namespace(|| format!("expose c[{}]", i)), c, 2, i)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn public_vec_and_test() {
+    let a = [0xFFu64, 0xA5, 0x0F, 0x00];
+    let b = [0x3Cu64, 0x5A, 0xFF, 0x81];
+    let column = |words: &[u64]| words.iter().map(|w| Fp::from(*w)).collect::<Vec<_>>();
+    let c: Vec<_> = a.iter().zip(&b).map(|(a, b)| a & b).collect();
+
+    let circuit = PublicVecAndCircuit::<8> { len: 4 };
+    let k = min_k_for_vec::<8>(4);
+
+    let prover = MockProver::run(k, &circuit, vec![column(&a), column(&b), column(&c)]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let mut wrong = c.clone();
+    wrong[2] ^= 1;
+    let prover =
+        MockProver::run(k, &circuit, vec![column(&a), column(&b), column(&wrong)]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+/// A host circuit that allocates every column itself and hands them to
+/// `AndChip::configure`, sharing the advice columns with a gate of its own
+/// that doubles the AND result before exposing it.
+#[cfg(test)]
+#[derive(Default)]
+struct HostCircuit {
+    a: Option<Fp>,
+    b: Option<Fp>,
+}
+
+#[cfg(test)]
+#[derive(Clone, Debug)]
+struct HostConfig {
+    and: AndConfig,
+    advice: [Column<Advice>; 2],
+    s_double: Selector,
+}
+
+#[cfg(test)]
+impl Circuit<Fp> for HostCircuit {
+    type Config = HostConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column()];
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+
+        let s_double = meta.selector();
+        meta.create_gate("double", |meta| {
+            let x = meta.query_advice(advice[0], Rotation::cur());
+            let out = meta.query_advice(advice[1], Rotation::cur());
+            let s_double = meta.query_selector(s_double);
+
+            vec![s_double * (Expression::Constant(Fp::from(2)) * x - out)]
+        });
+
+        let and = AndChip::<Fp, 8>::configure(meta, advice, vec![instance], constant);
+        HostConfig {
+            and,
+            advice,
+            s_double,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let chip = AndChip::<Fp, 8>::construct(config.and);
+        chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        let a = chip.load_private(layouter.namespace(|| "load a"), self.a)?;
+        let b = chip.load_private(layouter.namespace(|| "load b"), self.b)?;
+        let a_and_b = chip.and(layouter.namespace(|| "a & b"), a, b)?;
+
+        let doubled = layouter.assign_region(
+            || "double",
+            |mut region: Region<'_, Fp>| {
+                config.s_double.enable(&mut region, 0)?;
+                let x = a_and_b
+                    .0
+                    .copy_advice(|| "x", &mut region, config.advice[0], 0)?;
+                let value = x.value().map(|x| Fp::from(2) * *x);
+                region
+                    .assign_advice(
+                        || "2x",
+                        config.advice[1],
+                        0,
+                        || value.ok_or(Error::Synthesis),
+                    )
+                    .map(Word)
+            },
+        )?;
+
+        chip.expose_public(layouter.namespace(|| "expose 2 * a_and_b"), doubled, 0, 0)
+    }
+}
+
+#[test]
+fn host_circuit_test() {
+    let circuit = HostCircuit {
+        a: Some(Fp::from(0b1110)),
+        b: Some(Fp::from(0b0111)),
+    };
+
+    let prover = MockProver::run(5, &circuit, vec![vec![Fp::from(2 * 0b0110)]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let prover = MockProver::run(5, &circuit, vec![vec![Fp::from(0b0110)]]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+/// Commits to an operand as `H(a, r)`, with Poseidon over `P128Pow5T3`
+/// and a random blinding factor `r` keeping the commitment hiding.
+pub fn commit_operand(a: Fp, blind: Fp) -> Fp {
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<2>, 3, 2>::init().hash([a, blind])
+}
+
+/// Proves `a & b` for an `a` fixed beforehand by `commit_operand(a, blind)`.
+///
+/// The result is exposed at instance row 0 and the commitment at row 1. The
+/// witnessed `a` is hashed in-circuit, so proving with any other `a` than the
+/// committed one fails. On top of `MyCircuit`'s columns, the Poseidon chip
+/// takes three advice columns for its state, one for the partial S-box and
+/// six fixed columns of round constants, and the permutation about 40 rows.
+#[derive(Default)]
+pub struct CommittedAndCircuit<const WORD_BITS: u32 = 8> {
+    pub a: Option<Fp>,
+    pub blind: Option<Fp>,
+    pub b: Option<Fp>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CommittedAndConfig {
+    and: AndConfig,
+    poseidon: Pow5Config<Fp, 3, 2>,
+}
+
+impl<const WORD_BITS: u32> Circuit<Fp> for CommittedAndCircuit<WORD_BITS> {
+    type Config = CommittedAndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let and = MyCircuit::<Fp, WORD_BITS>::configure(meta);
+
+        let state = [
+            meta.advice_column(),
+            meta.advice_column(),
+            meta.advice_column(),
+        ];
+        let partial_sbox = meta.advice_column();
+        let rc_a = [
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+        ];
+        let rc_b = [
+            meta.fixed_column(),
+            meta.fixed_column(),
+            meta.fixed_column(),
+        ];
+        let poseidon = Pow5Chip::configure::<P128Pow5T3>(meta, state, partial_sbox, rc_a, rc_b);
+
+        CommittedAndConfig { and, poseidon }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let field_chip = AndChip::<Fp, WORD_BITS>::construct(config.and);
+        field_chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        let a = field_chip.load_private(layouter.namespace(|| "load a"), self.a)?;
+        let blind = field_chip.load_private(layouter.namespace(|| "load blind"), self.blind)?;
+        let b = field_chip.load_private(layouter.namespace(|| "load b"), self.b)?;
+
+        let hasher = PoseidonHash::<_, _, P128Pow5T3, ConstantLength<2>, 3, 2>::init(
+            Pow5Chip::construct(config.poseidon),
+            layouter.namespace(|| "init poseidon"),
+        )?;
+        let commitment = hasher.hash(layouter.namespace(|| "H(a, r)"), [a.0.clone(), blind.0])?;
+
+        let a_and_b = field_chip.and(layouter.namespace(|| "a & b"), a, b)?;
+
+        field_chip.expose_public(layouter.namespace(|| "expose a_and_b"), a_and_b, 0, 0)?;
+        field_chip.expose_public(
+            layouter.namespace(|| "expose commitment"),
+            Word(commitment),
+            0,
+            1,
+        )
+    }
+}
+
+#[test]
+fn committed_and_circuit_test() {
+    let k = 7;
+    let (a, blind, b) = (Fp::from(3), Fp::from(0x1234_5678), Fp::from(4));
+    let circuit = CommittedAndCircuit::<8> {
+        a: Some(a),
+        blind: Some(blind),
+        b: Some(b),
+    };
+
+    let public_inputs = vec![Fp::from(3 & 4), commit_operand(a, blind)];
+    let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // The same proof can't be passed off as being about a different operand.
+    let public_inputs = vec![Fp::from(3 & 4), commit_operand(Fp::from(7), blind)];
+    let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+/// Proves `((a & b) ^ c)` for `NARROW_BITS`-bit `a` and `b` and a
+/// `WIDE_BITS`-bit `c`: the AND on a narrow chip, the XOR on a wide one.
+///
+/// Both chips are configured over the same advice, instance and constants
+/// columns. Their gates and lookups sit behind their own selectors, so they
+/// don't interfere on shared rows. Each chip allocates and fills its own
+/// `even_bits` table column. The narrow table is a prefix of the wide one,
+/// so a single table would do, but a second table column costs no rows: `k`
+/// is set by the wide table either way. The result is exposed at instance
+/// row 0.
+#[derive(Default)]
+pub struct WideningCircuit<const NARROW_BITS: u32 = 8, const WIDE_BITS: u32 = 16> {
+    pub a: Option<Fp>,
+    pub b: Option<Fp>,
+    pub c: Option<Fp>,
+}
+
+#[derive(Clone, Debug)]
+pub struct WideningConfig {
+    narrow: AndConfig,
+    wide: AndConfig,
+}
+
+impl<const NARROW_BITS: u32, const WIDE_BITS: u32> Circuit<Fp>
+    for WideningCircuit<NARROW_BITS, WIDE_BITS>
+{
+    type Config = WideningConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column()];
+        let instance = vec![meta.instance_column()];
+        let constant = meta.fixed_column();
+
+        WideningConfig {
+            narrow: AndChip::<Fp, NARROW_BITS>::configure(meta, advice, instance.clone(), constant),
+            wide: AndChip::<Fp, WIDE_BITS>::configure(meta, advice, instance, constant),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let narrow = AndChip::<Fp, NARROW_BITS>::construct(config.narrow);
+        let wide = AndChip::<Fp, WIDE_BITS>::construct(config.wide);
+        narrow.alloc_table(&mut layouter.namespace(|| "alloc narrow table"))?;
+        wide.alloc_table(&mut layouter.namespace(|| "alloc wide table"))?;
+
+        let a = narrow.load_private(layouter.namespace(|| "load a"), self.a)?;
+        let b = narrow.load_private(layouter.namespace(|| "load b"), self.b)?;
+        let c = wide.load_private(layouter.namespace(|| "load c"), self.c)?;
+
+        let a_and_b = narrow.and(layouter.namespace(|| "a & b"), a, b)?;
+        let a_and_b = narrow.widen(layouter.namespace(|| "widen a & b"), &wide, a_and_b)?;
+        let out = wide.xor(layouter.namespace(|| "(a & b) ^ c"), a_and_b, c)?;
+
+        wide.expose_public(layouter.namespace(|| "expose out"), out, 0, 0)
+    }
+}
+
+#[test]
+fn widening_circuit_test() {
+    let k = 9;
+    for (a, b, c) in [(3, 6, 0xABCD), (0xFF, 0xF0, 0xFFFF), (0, 0xFF, 0x0100)] {
+        let circuit = WideningCircuit::<8, 16> {
+            a: Some(Fp::from(a)),
+            b: Some(Fp::from(b)),
+            c: Some(Fp::from(c)),
+        };
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from((a & b) ^ c)]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()), "({:x} & {:x}) ^ {:x}", a, b, c);
+
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(a & b)]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    // `a` is range-checked by the narrow chip, even though the wide one
+    // would take it.
+    let circuit = WideningCircuit::<8, 16> {
+        a: Some(Fp::from(0x100)),
+        b: Some(Fp::from(0x1FF)),
+        c: Some(Fp::zero()),
+    };
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(0x100)]]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+/// Proves `a & b` for operands fixed beforehand by public commitments to
+/// both: `commit_operand(a, blind_a)` and `commit_operand(b, blind_b)`.
+///
+/// The result is exposed at instance row 0, and the commitments to `a` and
+/// `b` at rows 1 and 2. Both openings are hashed in-circuit and copied into
+/// the AND, so the result is only provable for the committed operands. The
+/// columns are `CommittedAndCircuit`'s, and the second hash costs another
+/// Poseidon permutation, about 40 rows, which needs `k = 8`.
+#[derive(Default)]
+pub struct CommittedOperandsAndCircuit<const WORD_BITS: u32 = 8> {
+    pub a: Option<Fp>,
+    pub blind_a: Option<Fp>,
+    pub b: Option<Fp>,
+    pub blind_b: Option<Fp>,
+}
+
+impl<const WORD_BITS: u32> Circuit<Fp> for CommittedOperandsAndCircuit<WORD_BITS> {
+    type Config = CommittedAndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        CommittedAndCircuit::<WORD_BITS>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let field_chip = AndChip::<Fp, WORD_BITS>::construct(config.and);
+        field_chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        let mut operands = Vec::with_capacity(2);
+        for (name, value, blind) in [("a", self.a, self.blind_a), ("b", self.b, self.blind_b)] {
+            let word =
+                field_chip.load_private(layouter.namespace(|| format!("load {}", name)), value)?;
+            let blind = field_chip
+                .load_private(layouter.namespace(|| format!("load blind_{}", name)), blind)?;
+
+            let hasher = PoseidonHash::<_, _, P128Pow5T3, ConstantLength<2>, 3, 2>::init(
+                Pow5Chip::construct(config.poseidon.clone()),
+                layouter.namespace(|| format!("init poseidon for {}", name)),
+            )?;
+            let commitment = hasher.hash(
+                layouter.namespace(|| format!("H({}, r)", name)),
+                [word.0.clone(), blind.0],
+            )?;
+            operands.push((word, commitment));
+        }
+        let (b, b_commitment) = operands.pop().unwrap();
+        let (a, a_commitment) = operands.pop().unwrap();
+
+        let a_and_b = field_chip.and(layouter.namespace(|| "a & b"), a, b)?;
+
+        field_chip.expose_public(layouter.namespace(|| "expose a_and_b"), a_and_b, 0, 0)?;
+        field_chip.expose_public(
+            layouter.namespace(|| "expose a commitment"),
+            Word(a_commitment),
+            0,
+            1,
+        )?;
+        field_chip.expose_public(
+            layouter.namespace(|| "expose b commitment"),
+            Word(b_commitment),
+            0,
+            2,
+        )
+    }
+}
+
+#[test]
+fn committed_operands_and_circuit_test() {
+    let k = 8;
+    let (blind_a, blind_b) = (Fp::from(0x1234_5678), Fp::from(0x9abc_def0));
+    let circuit = |a: u64, b: u64| CommittedOperandsAndCircuit::<8> {
+        a: Some(Fp::from(a)),
+        blind_a: Some(blind_a),
+        b: Some(Fp::from(b)),
+        blind_b: Some(blind_b),
+    };
+    let commitments = [
+        commit_operand(Fp::from(3), blind_a),
+        commit_operand(Fp::from(4), blind_b),
+    ];
+    let public_inputs = |result: u64| vec![vec![Fp::from(result), commitments[0], commitments[1]]];
+
+    let prover = MockProver::run(k, &circuit(3, 4), public_inputs(0)).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(k, &circuit(3, 4), public_inputs(3)).unwrap();
+    assert!(prover.verify().is_err());
+
+    // An `a` other than the committed one gives a different commitment,
+    // even where it leaves `a & b` unchanged.
+    let prover = MockProver::run(k, &circuit(1, 4), public_inputs(0)).unwrap();
+    assert!(prover.verify().is_err());
+    let prover = MockProver::run(k, &circuit(3, 12), public_inputs(0)).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+/// The parent of two nodes of a Merkle tree, `H(left, right)` with the
+/// same Poseidon instance as `commit_operand`.
+pub fn merkle_parent(left: Fp, right: Fp) -> Fp {
+    poseidon::Hash::<_, P128Pow5T3, ConstantLength<2>, 3, 2>::init().hash([left, right])
+}
+
+/// The root of the Merkle tree over `leaves`, which are the vector elements
+/// themselves. Panics unless there is a power of two of them.
+pub fn merkle_root(leaves: &[Fp]) -> Fp {
+    assert!(
+        leaves.len().is_power_of_two(),
+        "a Merkle tree needs a power of two leaves, got {}",
+        leaves.len()
+    );
+
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| merkle_parent(pair[0], pair[1]))
+            .collect();
+    }
+    level[0]
+}
+
+/// The siblings on the way from leaf `index` up to the root of the tree over
+/// `leaves`, bottom first: the path `MerkleAndCircuit` takes.
+pub fn merkle_path(leaves: &[Fp], mut index: usize) -> Vec<Fp> {
+    assert!(leaves.len().is_power_of_two() && index < leaves.len());
+
+    let mut level = leaves.to_vec();
+    let mut path = vec![];
+    while level.len() > 1 {
+        path.push(level[index ^ 1]);
+        level = level
+            .chunks(2)
+            .map(|pair| merkle_parent(pair[0], pair[1]))
+            .collect();
+        index >>= 1;
+    }
+    path
+}
+
+/// Hashes `leaf` up a Merkle path in-circuit and returns the root it leads
+/// to, for the caller to constrain against a committed one.
+///
+/// This is the interface between the Merkle gadget and `AndChip`. The leaf,
+/// the siblings and the index bits are all `AndChip` words, least
+/// significant bit and lowest sibling first. At each level, `cswap` on the
+/// index bit puts the current node left of its sibling when the bit is 0 and
+/// right of it when it is 1, which also constrains the bit to be boolean.
+/// The pair is then copied into the Poseidon state columns and hashed, and
+/// the parent comes back as a `Word` living in a state column, which the
+/// next `cswap` copies into the chip's advice columns. Both sides have
+/// equality enabled on their columns, so the hand-off is copy constraints
+/// and nothing else.
+///
+/// The leaf is not range-checked here: whatever is done with it afterwards,
+/// an AND say, does that.
+pub fn merkle_inclusion<const WORD_BITS: u32>(
+    chip: &AndChip<Fp, WORD_BITS>,
+    poseidon: &Pow5Config<Fp, 3, 2>,
+    mut layouter: impl Layouter<Fp>,
+    leaf: Word<Fp>,
+    index_bits: &[Word<Fp>],
+    path: &[Word<Fp>],
+) -> Result<Word<Fp>, Error> {
+    assert_eq!(index_bits.len(), path.len(), "one index bit per level");
+
+    let mut node = leaf;
+    for (level, (bit, sibling)) in index_bits.iter().zip(path).enumerate() {
+        let (left, right) = chip.cswap(
+            layouter.namespace(|| format!("order level {}", level)),
+            bit.clone(),
+            node,
+            sibling.clone(),
+        )?;
+        let hasher = PoseidonHash::<_, _, P128Pow5T3, ConstantLength<2>, 3, 2>::init(
+            Pow5Chip::construct(poseidon.clone()),
+            layouter.namespace(|| format!("init poseidon for level {}", level)),
+        )?;
+        node = Word(hasher.hash(
+            layouter.namespace(|| format!("H(left, right) at level {}", level)),
+            [left.0, right.0],
+        )?);
+    }
+    Ok(node)
+}
+
+/// Proves `a[i] & b[i]` for vectors `a` and `b` of `2^DEPTH` words committed
+/// to by their Merkle roots, `merkle_root(a)` and `merkle_root(b)`.
+///
+/// The result is exposed at instance row 0, the roots of `a` and `b` at rows
+/// 1 and 2 and the index `i` at row 3. `a_path` and `b_path` are the
+/// `merkle_path`s of the two elements. Both are hashed up to their roots
+/// with `merkle_inclusion`, along the bits of the public index, and the
+/// elements are then ANDed as usual. The index is range-checked to `DEPTH`
+/// bits, so it can't name a leaf past the end of the vectors.
+///
+/// The columns are `CommittedAndCircuit`'s: the Poseidon chip takes three
+/// advice columns for its state, one for the partial S-box and six fixed
+/// columns of round constants on top of `MyCircuit`'s. Every level costs a
+/// permutation per vector, so `2 * DEPTH` of them; at `DEPTH = 2` that needs
+/// `k = 9`.
+pub struct MerkleAndCircuit<const WORD_BITS: u32 = 8, const DEPTH: usize = 2> {
+    pub a: Option<Fp>,
+    pub a_path: Vec<Option<Fp>>,
+    pub b: Option<Fp>,
+    pub b_path: Vec<Option<Fp>>,
+}
+
+impl<const WORD_BITS: u32, const DEPTH: usize> Default for MerkleAndCircuit<WORD_BITS, DEPTH> {
+    fn default() -> Self {
+        Self {
+            a: None,
+            a_path: vec![None; DEPTH],
+            b: None,
+            b_path: vec![None; DEPTH],
+        }
+    }
+}
+
+impl<const WORD_BITS: u32, const DEPTH: usize> Circuit<Fp> for MerkleAndCircuit<WORD_BITS, DEPTH> {
+    type Config = CommittedAndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        CommittedAndCircuit::<WORD_BITS>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        assert!(
+            DEPTH <= WORD_BITS as usize,
+            "a {}-level index doesn't fit in a {}-bit word",
+            DEPTH,
+            WORD_BITS
+        );
+        assert!(self.a_path.len() == DEPTH && self.b_path.len() == DEPTH);
+
+        let field_chip = AndChip::<Fp, WORD_BITS>::construct(config.and);
+        field_chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        // The index is public, and only its low `DEPTH` bits may be set.
+        let index = field_chip.load_public(layouter.namespace(|| "load index"), 0, 3)?;
+        let index_bits =
+            field_chip.decompose_bits(layouter.namespace(|| "decompose index"), index)?;
+        let high = field_chip.compose_bits(
+            layouter.namespace(|| "index high bits"),
+            &index_bits[DEPTH..],
+        )?;
+        let zero = field_chip.load_constant(layouter.namespace(|| "0"), Fp::zero())?;
+        field_chip.assert_equal(layouter.namespace(|| "index fits"), high, zero)?;
+        let index_bits = &index_bits[..DEPTH];
+
+        let mut operands = Vec::with_capacity(2);
+        for (name, value, path, row) in [
+            ("a", self.a, &self.a_path, 1),
+            ("b", self.b, &self.b_path, 2),
+        ] {
+            let word =
+                field_chip.load_private(layouter.namespace(|| format!("load {}", name)), value)?;
+            let path = path
+                .iter()
+                .enumerate()
+                .map(|(level, sibling)| {
+                    field_chip.load_private(
+                        layouter.namespace(|| format!("load {} sibling {}", name, level)),
+                        *sibling,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let root = merkle_inclusion(
+                &field_chip,
+                &config.poseidon,
+                layouter.namespace(|| format!("{} inclusion", name)),
+                word.clone(),
+                index_bits,
+                &path,
+            )?;
+            field_chip.expose_public(
+                layouter.namespace(|| format!("expose {} root", name)),
+                root,
+                0,
+                row,
+            )?;
+            operands.push(word);
+        }
+        let b = operands.pop().unwrap();
+        let a = operands.pop().unwrap();
+
+        let a_and_b = field_chip.and(layouter.namespace(|| "a & b"), a, b)?;
+        field_chip.expose_public(layouter.namespace(|| "expose a_and_b"), a_and_b, 0, 0)
+    }
+}
+
+#[test]
+fn merkle_and_circuit_test() {
+    let k = 9;
+    let leaves = |words: [u64; 4]| words.map(Fp::from);
+    let (a, b) = (
+        leaves([0x12, 0x34, 0xF0, 0x78]),
+        leaves([0xFF, 0x00, 0x3C, 0x55]),
+    );
+    let circuit = |index: usize, a_path: Vec<Fp>, b_path: Vec<Fp>| MerkleAndCircuit::<8, 2> {
+        a: Some(a[index]),
+        a_path: a_path.into_iter().map(Some).collect(),
+        b: Some(b[index]),
+        b_path: b_path.into_iter().map(Some).collect(),
+    };
+    let public_inputs = |result: u64, index: u64| {
+        vec![vec![
+            Fp::from(result),
+            merkle_root(&a),
+            merkle_root(&b),
+            Fp::from(index),
+        ]]
+    };
+
+    let valid = || circuit(2, merkle_path(&a, 2), merkle_path(&b, 2));
+    let prover = MockProver::run(k, &valid(), public_inputs(0xF0 & 0x3C, 2)).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(k, &valid(), public_inputs((0xF0 & 0x3C) ^ 1, 2)).unwrap();
+    assert!(prover.verify().is_err());
+
+    // The path of index 2 hashes up to the root only from index 2.
+    let prover = MockProver::run(k, &valid(), public_inputs(0xF0 & 0x3C, 3)).unwrap();
+    assert!(prover.verify().is_err());
+    // Nor past the end of the vectors, where the low bits would line up.
+    let prover = MockProver::run(k, &valid(), public_inputs(0xF0 & 0x3C, 6)).unwrap();
+    assert!(prover.verify().is_err());
+
+    // The path of another leaf, and a path with a sibling swapped out.
+    let wrong_path = circuit(2, merkle_path(&a, 1), merkle_path(&b, 2));
+    let prover = MockProver::run(k, &wrong_path, public_inputs(0xF0 & 0x3C, 2)).unwrap();
+    assert!(prover.verify().is_err());
+    let mut path = merkle_path(&b, 2);
+    path[0] = Fp::from(0x56);
+    let wrong_sibling = circuit(2, merkle_path(&a, 2), path);
+    let prover = MockProver::run(k, &wrong_sibling, public_inputs(0xF0 & 0x3C, 2)).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+/// Proves that a public word splits into the given even and odd lanes, with
+/// both lanes range-checked against the spread table.
+///
+/// The even lane is exposed at instance row 0, the odd lane at row 1 and the
+/// word itself at row 2.
+#[derive(Default)]
+pub struct DecomposeCircuit<const WORD_BITS: u32 = 8> {
+    pub value: Option<Fp>,
+}
+
+impl<const WORD_BITS: u32> Circuit<Fp> for DecomposeCircuit<WORD_BITS> {
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MyCircuit::<Fp, WORD_BITS>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let field_chip = AndChip::<Fp, WORD_BITS>::construct(config);
+        field_chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        let value = field_chip.load_private(layouter.namespace(|| "load value"), self.value)?;
+        let (even, odd) =
+            field_chip.verify_decompose(layouter.namespace(|| "decompose"), value.clone())?;
+
+        field_chip.expose_public(layouter.namespace(|| "expose even"), even, 0, 0)?;
+        field_chip.expose_public(layouter.namespace(|| "expose odd"), odd, 0, 1)?;
+        field_chip.expose_public(layouter.namespace(|| "expose value"), value, 0, 2)
+    }
+}
+
+#[test]
+fn decompose_circuit_test() {
+    let value = Fp::from(0xA5);
+    let circuit = DecomposeCircuit::<8> { value: Some(value) };
+
+    let (even, odd) = decompose(value);
+    assert_eq!((even, odd), (Fp::from(0x05), Fp::from(0x50)));
+
+    let prover = MockProver::run(5, &circuit, vec![vec![even, odd, value]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let prover = MockProver::run(5, &circuit, vec![vec![odd, even, value]]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn gate_coefficient_test() {
+    let mut meta = ConstraintSystem::<Fp>::default();
+    let config = MyCircuit::<Fp, 8>::configure(&mut meta);
+    assert_eq!(config.advice.map(|column| column.index()), [0, 1]);
+
+    // Evaluates a gate with its selector on, `lhs` and `rhs` on the current
+    // row and `out` below `lhs`.
+    let eval = |name: &str, lhs: u64, rhs: u64, out: u64| {
+        let gate = meta
+            .gates()
+            .iter()
+            .find(|gate| gate.name() == name)
+            .unwrap();
+        assert_eq!(gate.polynomials().len(), 1);
+        gate.polynomials()[0].evaluate(
+            &|c| c,
+            &|_| Fp::one(),
+            &|_, _, _| Fp::zero(),
+            &|_, column, rotation| match (column, rotation) {
+                (0, Rotation(0)) => Fp::from(lhs),
+                (1, Rotation(0)) => Fp::from(rhs),
+                (0, Rotation(1)) => Fp::from(out),
+                query => panic!("unexpected advice query {:?}", query),
+            },
+            &|_, _, _| Fp::zero(),
+            &|a| -a,
+            &|a, b| a + b,
+            &|a, b| a * b,
+            &|a, c| a * c,
+        )
+    };
+
+    for name in ["decompose", "compose"] {
+        // `lhs + 2 * rhs - out`, one term at a time.
+        assert_eq!(eval(name, 1, 0, 0), Fp::one(), "{}", name);
+        assert_eq!(eval(name, 0, 1, 0), Fp::from(2), "{}", name);
+        assert_eq!(eval(name, 0, 0, 1), -Fp::one(), "{}", name);
+    }
+}
+
+/// A cell a constraint reads, relative to the row it is applied at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Query {
+    /// The `n`th selector, numbered in the order the constraints first use
+    /// them.
+    Selector(usize),
+    Fixed {
+        column: usize,
+        rotation: i32,
+    },
+    Advice {
+        column: usize,
+        rotation: i32,
+    },
+    Instance {
+        column: usize,
+        rotation: i32,
+    },
+}
+
+/// `coefficient` times the product of `queries`, which are sorted and may
+/// repeat: `bit * (1 - bit)` has a `bit, bit` term.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Monomial {
+    pub coefficient: Fp,
+    pub queries: Vec<Query>,
+}
+
+/// A polynomial as a sum of distinct monomials, like terms merged and zero
+/// terms dropped.
+pub type Polynomial = Vec<Monomial>;
+
+/// A circuit's gates and lookups, expanded into plain polynomials over its
+/// cells, for tools that would rather not walk halo2's `Expression`s.
+///
+/// Every polynomial in `constraints` has to vanish on every row. A lookup is
+/// a list of `(input, table)` pairs, and on every row the inputs evaluated
+/// together have to match the tables evaluated together on some row.
+///
+/// `to_string` gives the same as text, one line per constraint or lookup:
+///
+/// ```text
+/// constraint add: 1*s0*a0@0 + 1*s0*a1@0 + -1*s0*a0@1
+/// lookup: 1*s1*a0@0 -> 1*f1@0
+/// ```
+///
+/// `sN` is selector `N`, and `aC@R`, `fC@R` and `iC@R` are the advice, fixed
+/// and instance cells in column `C` at rotation `R`, the column indices of
+/// each kind counted separately. Coefficients are in decimal, negative ones
+/// written as `-x` rather than `p - x`, and any other as the 64 hex digits
+/// of the field element's little-endian representation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConstraintMatrix {
+    /// `(gate name, polynomial)` for every constraint of every gate.
+    pub constraints: Vec<(String, Polynomial)>,
+    pub lookups: Vec<Vec<(Polynomial, Polynomial)>>,
+}
+
+/// Expands the gates and lookups `C` configures into a `ConstraintMatrix`.
+pub fn export_constraints_matrix<C: Circuit<Fp>>() -> ConstraintMatrix {
+    let mut meta = ConstraintSystem::default();
+    C::configure(&mut meta);
+
+    let selectors = std::cell::RefCell::new(Vec::new());
+    let expand = |expression: &Expression<Fp>| -> Polynomial {
+        let term = |query: Query| {
+            vec![Monomial {
+                coefficient: Fp::one(),
+                queries: vec![query],
+            }]
+        };
+        let scale = |terms: Polynomial, c: Fp| -> Polynomial {
+            terms
+                .into_iter()
+                .map(|m| Monomial {
+                    coefficient: m.coefficient * c,
+                    queries: m.queries,
+                })
+                .collect()
+        };
+        let terms = expression.evaluate(
+            &|c| {
+                vec![Monomial {
+                    coefficient: c,
+                    queries: vec![],
+                }]
+            },
+            &|selector| {
+                let mut selectors = selectors.borrow_mut();
+                let index = selectors
+                    .iter()
+                    .position(|s| *s == selector)
+                    .unwrap_or_else(|| {
+                        selectors.push(selector);
+                        selectors.len() - 1
+                    });
+                term(Query::Selector(index))
+            },
+            &|_, column, rotation: Rotation| {
+                term(Query::Fixed {
+                    column,
+                    rotation: rotation.0,
+                })
+            },
+            &|_, column, rotation: Rotation| {
+                term(Query::Advice {
+                    column,
+                    rotation: rotation.0,
+                })
+            },
+            &|_, column, rotation: Rotation| {
+                term(Query::Instance {
+                    column,
+                    rotation: rotation.0,
+                })
+            },
+            &|a| scale(a, -Fp::one()),
+            &|a, b| a.into_iter().chain(b).collect(),
+            &|a, b| {
+                a.iter()
+                    .flat_map(|x| {
+                        b.iter().map(move |y| Monomial {
+                            coefficient: x.coefficient * y.coefficient,
+                            queries: x.queries.iter().chain(&y.queries).copied().collect(),
+                        })
+                    })
+                    .collect()
+            },
+            &scale,
+        );
+
+        // Merge like terms, keeping the order they first appear in.
+        let mut merged: Polynomial = Vec::new();
+        for mut monomial in terms {
+            monomial.queries.sort();
+            match merged.iter_mut().find(|m| m.queries == monomial.queries) {
+                Some(m) => m.coefficient += monomial.coefficient,
+                None => merged.push(monomial),
+            }
+        }
+        merged.retain(|m| !bool::from(m.coefficient.is_zero()));
+        merged
+    };
+
+    let constraints = meta
+        .gates()
+        .iter()
+        .flat_map(|gate| {
+            gate.polynomials()
+                .iter()
+                .map(|polynomial| (gate.name().to_string(), expand(polynomial)))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let lookups = meta
+        .lookups()
+        .iter()
+        .map(|lookup| {
+            lookup
+                .input_expressions()
+                .iter()
+                .zip(lookup.table_expressions())
+                .map(|(input, table)| (expand(input), expand(table)))
+                .collect()
+        })
+        .collect();
+
+    ConstraintMatrix {
+        constraints,
+        lookups,
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Query::Selector(n) => write!(f, "s{}", n),
+            Query::Fixed { column, rotation } => write!(f, "f{}@{}", column, rotation),
+            Query::Advice { column, rotation } => write!(f, "a{}@{}", column, rotation),
+            Query::Instance { column, rotation } => write!(f, "i{}@{}", column, rotation),
+        }
+    }
+}
+
+impl fmt::Display for Monomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let small = |c: Fp| Some(c.get_lower_128()).filter(|low| Fp::from_u128(*low) == c);
+        match (small(self.coefficient), small(-self.coefficient)) {
+            (Some(c), _) => write!(f, "{}", c)?,
+            (None, Some(c)) => write!(f, "-{}", c)?,
+            (None, None) => {
+                for byte in self.coefficient.to_repr().iter() {
+                    write!(f, "{:02x}", byte)?;
+                }
+            }
+        }
+        for query in &self.queries {
+            write!(f, "*{}", query)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ConstraintMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let polynomial = |p: &Polynomial| {
+            if p.is_empty() {
+                return "0".to_string();
+            }
+            p.iter()
+                .map(|m| m.to_string())
+                .collect::<Vec<_>>()
+                .join(" + ")
+        };
+        for (name, p) in &self.constraints {
+            writeln!(f, "constraint {}: {}", name, polynomial(p))?;
+        }
+        for lookup in &self.lookups {
+            let pairs: Vec<_> = lookup
+                .iter()
+                .map(|(input, table)| format!("{} -> {}", polynomial(input), polynomial(table)))
+                .collect();
+            writeln!(f, "lookup: {}", pairs.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn export_constraints_matrix_test() {
+    let matrix = export_constraints_matrix::<MyCircuit<Fp, 8>>();
+
+    // One per gate, but two each for `bits`, `add carry` and `is equal`.
+    assert_eq!(matrix.constraints.len(), 11);
+    // Both operand lanes of `decompose` against `even_bits`.
+    assert_eq!(matrix.lookups.len(), 2);
+    assert!(matrix.lookups.iter().all(|lookup| lookup.len() == 1));
+
+    let text = matrix.to_string();
+    assert!(
+        text.starts_with("constraint add: 1*s0*a0@0 + 1*s0*a1@0 + -1*s0*a0@1\n"),
+        "{}",
+        text
+    );
+    assert!(
+        text.contains("constraint decompose: 1*s1*a0@0 + 2*s1*a1@0 + -1*s1*a0@1\n"),
+        "{}",
+        text
+    );
+    assert!(text.contains("lookup: 1*s1*a0@0 -> "), "{}", text);
+    // `2^8` is small enough to print in decimal.
+    assert!(text.contains("-256*"), "{}", text);
+
+    let lookup = export_constraints_matrix::<LookupAndCircuit<8>>();
+    assert_eq!(lookup.constraints.len(), matrix.constraints.len());
+    assert_eq!(lookup.lookups.len(), 3);
+    assert_eq!(lookup.lookups[2].len(), 3);
+}
+
+/// Rows at the bottom of every column that halo2 reserves for blinding
+/// factors and `l_last`, and that no gate or table may use. With no advice
+/// column queried at more than three points, this chip needs 5 + 1.
+const UNUSABLE_ROWS: usize = 6;
+
+/// Advice rows taken by one `and`: four decompositions, two adds and a
+/// compose, at two rows each.
+const AND_ROWS: usize = 14;
+/// The smallest `k` that fits `len` independent ANDs, each loading its two
+/// operands with `load_private`, next to the spread table:
+///
+/// `2^k - UNUSABLE_ROWS >= max(len * (2 + AND_ROWS), 2^(WORD_BITS / 2))`
+///
+/// The advice regions are laid out one after the other, while the table sits
+/// in its own column, so whichever of the two is taller decides `k`.
+pub fn min_k_for_vec<const WORD_BITS: u32>(len: usize) -> u32 {
+    let rows = std::cmp::max(len * (2 + AND_ROWS), 1 << (WORD_BITS / 2)) + UNUSABLE_ROWS;
+    usize::BITS - (rows - 1).leading_zeros()
+}
+
+/// The smallest `k` for a single AND, as in `MyCircuit`.
+///
+/// `min_k_for_vec::<WORD_BITS>(0)` is the smallest `k` the spread table fits
+/// in on its own.
+pub fn min_k<const WORD_BITS: u32>() -> u32 {
+    min_k_for_vec::<WORD_BITS>(1)
+}
+
+#[test]
+fn min_k_test() {
+    assert_eq!(min_k::<8>(), 5);
+    assert_eq!(min_k::<16>(), 9);
+    assert_eq!(min_k::<24>(), 13);
+    assert_eq!(min_k_for_vec::<8>(1), min_k::<8>());
+
+    let ks: Vec<_> = (1..=200).map(min_k_for_vec::<8>).collect();
+    assert!(ks.windows(2).all(|w| w[0] <= w[1]));
+    assert!(ks[0] < ks[199]);
+    // 100 ANDs take 1600 rows, plus the unusable ones.
+    assert_eq!(min_k_for_vec::<8>(100), 11);
+}
+
+#[test]
+fn my_circuit_required_k_test() {
+    let circuit = |output_row| MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(3)),
+        b: Some(Fp::from(6)),
+        output_row,
+    };
+    assert_eq!(circuit(0).required_k(), 5);
+    assert_eq!(circuit(0).required_k(), min_k::<8>());
+    // Row 25 is the last usable one at k = 5.
+    assert_eq!(circuit(25).required_k(), 5);
+    assert_eq!(circuit(26).required_k(), 6);
+    assert_eq!(MyCircuit::<Fp, 16>::default().required_k(), min_k::<16>());
+
+    for output_row in [0, 25, 26, 40] {
+        let circuit = circuit(output_row);
+        let mut public_inputs = vec![Fp::zero(); output_row + 1];
+        public_inputs[output_row] = Fp::from(3 & 6);
+        let prover = MockProver::run(circuit.required_k(), &circuit, vec![public_inputs]).unwrap();
+        assert_eq!(prover.verify(), Ok(()), "output_row {}", output_row);
+    }
+}
+
+/// Allocates the spread table and nothing else.
+#[cfg(test)]
+struct TableOnlyCircuit<const WORD_BITS: u32>;
+
+#[cfg(test)]
+impl<const WORD_BITS: u32> Circuit<Fp> for TableOnlyCircuit<WORD_BITS> {
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MyCircuit::<Fp, WORD_BITS>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let chip = AndChip::<Fp, WORD_BITS>::construct(config);
+        chip.alloc_table(&mut layouter)?;
+        Ok(())
+    }
+}
+
+/// Runs `TableOnlyCircuit` at the smallest `k` the table fits in, and at
+/// one below it, where the table runs into the unusable rows.
+#[cfg(test)]
+fn table_min_k_case<const WORD_BITS: u32>() -> u32 {
+    let k = min_k_for_vec::<WORD_BITS>(0);
+    let prover = MockProver::run(k, &TableOnlyCircuit::<WORD_BITS>, vec![vec![]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()), "WORD_BITS = {}", WORD_BITS);
+    assert!(
+        matches!(
+            MockProver::run(k - 1, &TableOnlyCircuit::<WORD_BITS>, vec![vec![]]),
+            Err(Error::NotEnoughRowsAvailable { .. })
+        ),
+        "WORD_BITS = {}",
+        WORD_BITS
+    );
+    k
+}
+
+#[test]
+fn table_min_k_test() {
+    // The table has `2^(WORD_BITS / 2)` rows, one per lane value, and they
+    // count against `2^k - UNUSABLE_ROWS`. At `WORD_BITS = 8` that is 16
+    // rows, not 256, which is why the tests get away with `k = 5`.
+    assert_eq!(table_min_k_case::<4>(), 4);
+    assert_eq!(table_min_k_case::<8>(), 5);
+    assert_eq!(table_min_k_case::<12>(), 7);
+    assert_eq!(table_min_k_case::<16>(), 9);
+}
+
+/// Splits a field element into its even and odd lanes, the odd one shifted
+/// down a bit, as `verify_decompose` witnesses them.
+///
+/// This is exact over the whole canonical representation, so `even + 2 *
+/// odd` gives the element back whatever its width. The chip relies on that
+/// for words that don't fit: their lanes are witnessed as they are and
+/// rejected by the lookups, not by the witness generation. Host-side code
+/// that expects a `WORD_BITS`-bit word should use `decompose_word`.
+///
+/// The representation is taken to be little-endian, as it is for the pasta
+/// fields and bn256.
+fn decompose<F: FieldExt>(word: F) -> (F, F) {
+    let mut even_only = word.to_repr();
+    even_only.as_mut().iter_mut().for_each(|bits| {
+        *bits &= 0b01010101;
+    });
+
+    let mut odd_only = word.to_repr();
+    odd_only.as_mut().iter_mut().for_each(|bits| {
+        *bits &= 0b10101010;
+    });
+    let odd_only = odd_only.as_ref();
+
+    // Shift the odd bits down one place across the whole representation,
+    // carrying each byte's low bit into the top of the byte below.
+    let mut odd_lane = F::Repr::default();
+    for (i, lane) in odd_lane.as_mut().iter_mut().enumerate() {
+        let carry = odd_only.get(i + 1).map_or(0, |above| above << 7);
+        *lane = (odd_only[i] >> 1) | carry;
+    }
+
+    // Masking bits off a canonical representation leaves it canonical.
+    let even_only = F::from_repr(even_only).unwrap();
+    let odd_lane = F::from_repr(odd_lane).unwrap();
+
+    (even_only, odd_lane)
+}
+
+/// `decompose` for a value that has to be a `WORD_BITS`-bit word, failing
+/// with `AndError::OutOfRange` for one that isn't rather than returning
+/// lanes the chip's lookups would reject.
+pub fn decompose_word<F: FieldExt, const WORD_BITS: u32>(word: F) -> Result<(F, F), AndError> {
+    if word_value::<F, WORD_BITS>(&word).is_none() {
+        return Err(AndError::out_of_range(&word, WORD_BITS));
+    }
+    Ok(decompose(word))
+}
+
+#[test]
+fn decompose_word_test() {
+    assert_eq!(
+        decompose_word::<_, 8>(Fp::from(0xA5)).unwrap(),
+        (Fp::from(0x05), Fp::from(0x50))
+    );
+    assert_eq!(
+        decompose_word::<_, 8>(Fp::from(0xFF)).unwrap(),
+        decompose(Fp::from(0xFF))
+    );
+
+    for value in [Fp::from(0x100), Fp::from_u128(1 << 100), -Fp::one()] {
+        assert!(
+            matches!(
+                decompose_word::<_, 8>(value),
+                Err(AndError::OutOfRange { value: v, word_bits: 8 }) if v == value.to_repr()
+            ),
+            "{:?}",
+            value
+        );
+    }
+
+    // Bits past 128 land in the right lanes instead of being cut off.
+    let two_128 = Fp::from_u128(1 << 127) * Fp::from(2);
+    let word = two_128 * Fp::from(0b110);
+    assert_eq!(decompose(word), (two_128 * Fp::from(0b100), two_128));
+    let minus_one = -Fp::one();
+    let (even, odd) = decompose(minus_one);
+    assert_eq!(even + Fp::from(2) * odd, minus_one);
+}
+
+/// Runs the host-side half of `MyCircuit::synthesize` for `a & b`, step by
+/// step as `and` assigns them, and returns every intermediate value as a
+/// JSON object keyed by the name it is witnessed under.
+///
+/// The keys come out in synthesis order. This is meant for diffing against
+/// a trace from another implementation, and needs no circuit.
+pub fn export_witness_json(a: u64, b: u64) -> String {
+    let (ae, ao) = decompose(Fp::from(a));
+    let (be, bo) = decompose(Fp::from(b));
+    let e = ae + be;
+    let o = ao + bo;
+    let (even_xor, even_and) = decompose(e);
+    let (odd_xor, odd_and) = decompose(o);
+    let a_and_b = even_and + Fp::from(2) * odd_and;
+
+    let values = [
+        ("a", Fp::from(a)),
+        ("b", Fp::from(b)),
+        ("ae", ae),
+        ("ao", ao),
+        ("be", be),
+        ("bo", bo),
+        ("ae + be", e),
+        ("ao + bo", o),
+        ("even_xor", even_xor),
+        ("even_and", even_and),
+        ("odd_xor", odd_xor),
+        ("odd_and", odd_and),
+        ("a & b", a_and_b),
+    ];
+    let fields: Vec<_> = values
+        .iter()
+        .map(|(name, value)| format!("  \"{}\": {}", name, value.get_lower_128()))
+        .collect();
+    format!("{{\n{}\n}}", fields.join(",\n"))
+}
+
+#[test]
+fn export_witness_json_test() {
+    let json = export_witness_json(3, 4);
+    assert!(json.starts_with("{\n") && json.ends_with("\n}"));
+
+    // 3 = 0b011 splits into 1 on both lanes, 4 = 0b100 into 4 on the even
+    // one. Neither lane sum has a 2-bit lane at 2 or more, so no AND bits.
+    for field in [
+        "\"ae\": 1,",
+        "\"ao\": 1,",
+        "\"be\": 4,",
+        "\"bo\": 0,",
+        "\"ae + be\": 5,",
+        "\"ao + bo\": 1,",
+        "\"even_and\": 0,",
+        "\"odd_and\": 0,",
+        "\"a & b\": 0\n",
+    ] {
+        assert!(json.contains(field), "{} missing from {}", field, json);
+    }
+
+    let json = export_witness_json(0xA5, 0x3C);
+    assert!(json.ends_with(&format!("\"a & b\": {}\n}}", 0xA5 & 0x3C)));
+}
+
+#[test]
+fn compose_decompose_test() {
+    // A handful of samples: each one is a MockProver run.
+    let words = (0..2u64.pow(8), 1..2u64.pow(8));
+    run_proptest(ProptestConfig::with_cases(32), words, |(a, delta)| {
+        prop_assert_eq!(
+            instruction_test::<8>(Instruction::DecomposeCompose, &[a], &[a]),
+            Ok(())
+        );
+        let wrong = (a + delta) % 2u64.pow(8);
+        prop_assert!(instruction_test::<8>(Instruction::DecomposeCompose, &[a], &[wrong]).is_err());
+        Ok(())
+    });
+}
+
+#[test]
+fn decompose_test_even_odd() {
+    let odds = 0xAAAA;
+    let evens = 0x5555;
+    let (e, o) = decompose(Fp::from_u128(odds));
+    assert_eq!(e.get_lower_128(), 0);
+    assert_eq!(o.get_lower_128(), odds >> 1);
+    let (e, o) = decompose(Fp::from_u128(evens));
+    assert_eq!(e.get_lower_128(), evens);
+    assert_eq!(o.get_lower_128(), 0);
+}
+
+/// The lookups only accept lanes that `alloc_table` loads, so `decompose`
+/// and `even_bits_at` have to agree on the spread form: if they drifted
+/// apart, every valid word would be rejected. Checks each lane of every word
+/// at 8 and 16 bits against the full table, and its sub-lanes against the
+/// columns of a 2-column one.
+#[test]
+fn decompose_lanes_in_table_test() {
+    fn check<const WORD_BITS: u32>() {
+        let entries = |table: SpreadTable, column: u32| {
+            table
+                .entries::<Fp, WORD_BITS>(column)
+                .map(|entry| entry.get_lower_128())
+                .collect::<BTreeSet<_>>()
+        };
+        let full = entries(SpreadTable::Full, 0);
+        assert_eq!(full.len(), SpreadTable::Full.rows::<WORD_BITS>());
+        let split = SpreadTable::Columns(2);
+        let columns = [entries(split, 0), entries(split, 1)];
+        assert!(columns
+            .iter()
+            .all(|column| column.len() == split.rows::<WORD_BITS>()));
+
+        for word in 0..1u64 << WORD_BITS {
+            let (even, odd) = decompose(Fp::from(word));
+            for lane in [even, odd] {
+                assert!(
+                    full.contains(&lane.get_lower_128()),
+                    "{}-bit {:#x}: {:?}",
+                    WORD_BITS,
+                    word,
+                    lane
+                );
+
+                let sub_lanes = split.sub_lanes::<Fp, WORD_BITS>(lane);
+                assert_eq!(sub_lanes.iter().fold(Fp::zero(), |sum, s| sum + s), lane);
+                for (column, sub_lane) in columns.iter().zip(sub_lanes) {
+                    assert!(
+                        column.contains(&sub_lane.get_lower_128()),
+                        "{}-bit {:#x}: {:?}",
+                        WORD_BITS,
+                        word,
+                        lane
+                    );
+                }
+            }
+        }
+    }
+
+    check::<8>();
+    check::<16>();
+}
+
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use proptest::{
+    strategy::ValueTree,
+    test_runner::{RngAlgorithm, TestCaseResult, TestRng, TestRunner},
+};
+
+/// The seed `run_proptest` runs from: `PROPTEST_SEED` if it is set, and a
+/// fresh random one otherwise.
+#[cfg(test)]
+fn proptest_seed() -> u64 {
+    match std::env::var("PROPTEST_SEED") {
+        Ok(seed) => seed.parse().expect("PROPTEST_SEED must be a u64"),
+        Err(_) => OsRng.next_u64(),
+    }
+}
+
+#[cfg(test)]
+fn seeded_runner(config: ProptestConfig, seed: u64) -> TestRunner {
+    let mut bytes = [0; 32];
+    bytes[..8].copy_from_slice(&seed.to_le_bytes());
+    TestRunner::new_with_rng(config, TestRng::from_seed(RngAlgorithm::ChaCha, &bytes))
+}
+
+/// Runs `test` over `config.cases` values of `strategy`, like a `proptest!`
+/// block, but from the seed `proptest_seed` picks.
+///
+/// `proptest!` reseeds on every run, so a failure can only be replayed from
+/// its regression file. This reports the seed instead, and rerunning the
+/// test with `PROPTEST_SEED=<seed>` draws the exact same inputs again. The
+/// regression files are turned off, as they would be replayed first.
+#[cfg(test)]
+fn run_proptest<S: Strategy>(
+    config: ProptestConfig,
+    strategy: S,
+    test: impl Fn(S::Value) -> TestCaseResult,
+) {
+    let seed = proptest_seed();
+    let config = ProptestConfig {
+        failure_persistence: None,
+        ..config
+    };
+    if let Err(failure) = seeded_runner(config, seed).run(&strategy, test) {
+        panic!("{}\nreplay with PROPTEST_SEED={}", failure, seed);
+    }
+}
+
+#[test]
+fn proptest_seed_test() {
+    let draws = |seed| {
+        let mut runner = seeded_runner(ProptestConfig::default(), seed);
+        (0..10)
+            .map(|_| {
+                (0..u128::MAX, 0..2u64.pow(8))
+                    .new_tree(&mut runner)
+                    .unwrap()
+                    .current()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(draws(42), draws(42));
+    assert_ne!(draws(42), draws(43));
+}
+
+#[test]
+fn decompose_test() {
+    // `hi * 2^128 + lo` reaches every element of the field, wrapping mod p.
+    let shift = Fp::from_u128(u128::MAX) + Fp::one();
+    let words = (any::<u128>(), any::<u128>())
+        .prop_map(move |(hi, lo)| Fp::from_u128(hi) * shift + Fp::from_u128(lo));
+
+    run_proptest(ProptestConfig::default(), words, |a| {
+        let (even, odd) = decompose(a);
+        prop_assert_eq!(even + odd + odd, a);
+        // Both lanes are spread values, with nothing in the odd positions.
+        for lane in [even, odd] {
+            prop_assert!(lane.to_repr().iter().all(|byte| byte & 0b10101010 == 0));
+        }
+        Ok(())
+    });
+
+    // `p - 1` has its top bit, 254, set: it ends up in the even lane, past
+    // where a 128-bit split would have cut it off.
+    let top = -Fp::one();
+    let (even, odd) = decompose(top);
+    assert_eq!(even + odd + odd, top);
+    assert_eq!(even.to_repr()[31], 0x40);
+}
+
+#[test]
+fn fp_u128_test() {
+    run_proptest(ProptestConfig::default(), 0..u128::MAX, |n| {
+        let a = Fp::from_u128(n);
+        let b = a.get_lower_128();
+        assert_eq!(b, n);
+        Ok(())
+    });
+}
+
+// proptest does not support testing const generics.
+#[test]
+fn all_8_bit_words_mock_prover_test() {
+    let words = (0..2u64.pow(8), 0..2u64.pow(8));
+    run_proptest(ProptestConfig::default(), words, |(a, b)| {
+        mock_prover_test::<8>(a, b);
+        Ok(())
+    });
+}
+
+#[test]
+fn all_8_bit_words_test() {
+    let words = (0..2u64.pow(8), 0..2u64.pow(8));
+    run_proptest(ProptestConfig::with_cases(50), words, |(a, b)| {
+        let c = a & b;
+        gen_proof_and_verify::<8>(a, b, c);
+        Ok(())
+    });
+}
+
+#[test]
+fn all_16_bit_words_mock_prover_test() {
+    let words = (0..2u64.pow(16), 0..2u64.pow(16));
+    run_proptest(ProptestConfig::with_cases(50), words, |(a, b)| {
+        mock_prover_test::<16>(a, b);
+        Ok(())
+    });
+}
+
+#[test]
+fn all_16_bit_words_test() {
+    let words = (0..2u64.pow(16), 0..2u64.pow(16));
+    run_proptest(ProptestConfig::with_cases(50), words, |(a, b)| {
+        let c = a & b;
+        gen_proof_and_verify::<16>(a, b, c);
+        Ok(())
+    });
+}
+
+#[test]
+#[should_panic]
+fn all_8_bit_words_test_bad_proof() {
+    let words = (0..2u64.pow(8), 0..2u64.pow(8), 0..2u64.pow(8));
+    run_proptest(ProptestConfig::with_cases(50), words, |(a, b, c)| {
+        prop_assume!(c != a & b);
+        gen_proof_and_verify::<8>(a, b, c);
+        Ok(())
+    });
+}
+
+#[test]
+#[should_panic]
+fn all_16_bit_words_test_bad_proof() {
+    let words = (0..2u64.pow(16), 0..2u64.pow(16), 0..2u64.pow(16));
+    run_proptest(ProptestConfig::with_cases(50), words, |(a, b, c)| {
+        prop_assume!(c != a & b);
+        gen_proof_and_verify::<16>(a, b, c);
+        Ok(())
+    });
+}
+
+#[test]
+#[should_panic]
+fn all_24_bit_words_test_bad_proof() {
+    let words = (0..2u64.pow(24), 0..2u64.pow(24), 0..2u64.pow(24));
+    run_proptest(ProptestConfig::with_cases(50), words, |(a, b, c)| {
+        prop_assume!(c != a & b);
+        gen_proof_and_verify::<24>(a, b, c);
+        Ok(())
+    });
+}
+
+// The case number was picked to run all tests in about 60 seconds on my machine.
+// TODO use `plonk::BatchVerifier` to speed up tests.
+#[test]
+fn all_24_bit_words_mock_prover_test() {
+    let words = (0..2u64.pow(24), 0..2u64.pow(24));
+    run_proptest(ProptestConfig::with_cases(20), words, |(a, b)| {
+        mock_prover_test::<24>(a, b);
+        Ok(())
+    });
+}
+
+#[test]
+fn all_24_bit_words_test() {
+    let words = (0..2u64.pow(24), 0..2u64.pow(24));
+    run_proptest(ProptestConfig::with_cases(20), words, |(a, b)| {
+        let c = a & b;
+        gen_proof_and_verify::<24>(a, b, c);
+        Ok(())
+    });
+}
+
+// It's used in the proptests
+#[cfg(test)]
+fn mock_prover_test<const WORD_BITS: u32>(a: u64, b: u64) {
+    let circuit: MyCircuit<Fp, WORD_BITS> = MyCircuit {
+        a: Some(Fp::from(a)),
+        b: Some(Fp::from(b)),
+        output_row: 0,
+    };
+    let k = circuit.required_k();
+
+    let c = Fp::from(a & b);
+
+    // Arrange the public input. We expose the bitwise AND result in row 0
+    // of the instance column, so we position it there in our public inputs.
+    let public_inputs = vec![c];
+
+    // Given the correct public input, our circuit will verify.
+    let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+/// Checks `MyCircuit` at `WORD_BITS` against `a & b` for every `step`th
+/// operand pair, at the `k` given by `min_k`. A wrong result, with its lowest
+/// bit flipped, must not verify.
+#[cfg(test)]
+fn and_width_test<const WORD_BITS: u32>(step: usize) {
+    let k = min_k::<WORD_BITS>();
+    for a in (0..1u64 << WORD_BITS).step_by(step) {
+        for b in (0..1u64 << WORD_BITS).step_by(step) {
+            let circuit = MyCircuit::<Fp, WORD_BITS> {
+                a: Some(Fp::from(a)),
+                b: Some(Fp::from(b)),
+                output_row: 0,
+            };
+
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(a & b)]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()), "{}-bit {} & {}", WORD_BITS, a, b);
+
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::from((a & b) ^ 1)]]).unwrap();
+            assert!(prover.verify().is_err(), "{}-bit {} & {}", WORD_BITS, a, b);
+        }
+    }
+}
+
+#[test]
+fn and_all_widths_test() {
+    // The smallest word: a single even and a single odd bit.
+    and_width_test::<2>(1);
+    and_width_test::<4>(1);
+    and_width_test::<8>(17);
+}
+
+/// Nothing in the chip is tied to `Fp`: the same AND runs over pasta's
+/// other field.
+#[test]
+fn fq_mock_prover_test() {
+    use pasta_curves::Fq;
+
+    let circuit = MyCircuit::<Fq, 8> {
+        a: Some(Fq::from(0xA5)),
+        b: Some(Fq::from(0x3C)),
+        output_row: 0,
+    };
+    let prover = MockProver::run(
+        circuit.required_k(),
+        &circuit,
+        vec![vec![Fq::from(0xA5 & 0x3C)]],
+    )
+    .unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    let prover = MockProver::run(
+        circuit.required_k(),
+        &circuit,
+        vec![vec![Fq::from(0xA5 | 0x3C)]],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+
+    // Exact over the whole representation, not only the low 128 bits.
+    let x = -Fq::one();
+    let (even, odd) = decompose(x);
+    assert_eq!(even + odd + odd, x);
+}
+
+/// `load_private_checked` over `Fq`: `AndError::OutOfRange` doesn't tie the
+/// chip to `Fp` either.
+#[test]
+fn fq_load_private_checked_test() {
+    use pasta_curves::Fq;
+
+    let result = &std::cell::RefCell::new(None);
+    let circuit = |value: Fq| {
+        chip_call(move |chip, layouter| {
+            chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+            let loaded = chip.load_private_checked(layouter.namespace(|| "load"), Some(value));
+            *result.borrow_mut() = Some(loaded.map(|_| ()));
+            Ok(())
+        })
+    };
+
+    let prover = MockProver::run(5, &circuit(Fq::from(255)), vec![vec![]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    assert!(matches!(result.take(), Some(Ok(()))));
+
+    for value in [Fq::from(256), -Fq::one()] {
+        MockProver::run(5, &circuit(value), vec![vec![]]).unwrap();
+        assert!(matches!(
+            result.take(),
+            Some(Err(AndError::OutOfRange { value: v, word_bits: 8 })) if v == value.to_repr()
+        ));
+    }
+    assert!(matches!(
+        decompose_word::<_, 8>(-Fq::one()),
+        Err(AndError::OutOfRange { word_bits: 8, .. })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "AndChip requires an even WORD_BITS of at least 2, got 1")]
+fn one_bit_word_rejected_test() {
+    mock_prover_test::<1>(1, 1);
+}
+
+#[test]
+#[should_panic(expected = "AndChip requires an even WORD_BITS of at least 2, got 3")]
+fn odd_word_bits_rejected_test() {
+    mock_prover_test::<3>(5, 3);
+}
+
+#[test]
+fn zeros_mock_prover_test() {
+    const WORD_BITS: u32 = 24;
+    let a = 0;
+    let b = 0;
+    let circuit = MyCircuit::<Fp, WORD_BITS> {
+        a: Some(Fp::from(a)),
+        b: Some(Fp::from(b)),
+        output_row: 0,
+    };
+    let k = circuit.required_k();
+
+    let c = Fp::from(a & b);
+
+    // Arrange the public input. We expose the bitwise AND result in row 0
+    // of the instance column, so we position it there in our public inputs.
+    let public_inputs = vec![c];
+
+    // Given the correct public input, our circuit will verify.
+    let prover = MockProver::run(k, &circuit, vec![public_inputs]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+/// The instructions `InstructionCircuit` knows how to run.
+#[cfg(test)]
+#[derive(Clone, Copy, Debug)]
+enum Instruction {
+    And,
+    AndAll,
+    /// `and_accumulate` folded over all the inputs, the first one as seed.
+    AndFold,
+    ColumnAnd,
+    Xor,
+    XorFold,
+    /// `not`, then `not` again: outputs `!a` and `a`.
+    Not,
+    Rotl(u32),
+    AndRotated(u32),
+    Morton,
+    Bits(u32, u32),
+    SetBit,
+    Shr(u32),
+    ShlVar,
+    ModPow2(u32),
+    ToGray,
+    FromGray,
+    /// `from_gray(to_gray(a))`.
+    GrayRoundTrip,
+    ZeroExtend(u32),
+    SignExtend(u32),
+    ThetaLane,
+    Majority,
+    Parity,
+    Popcount,
+    CountRuns,
+    Hamming,
+    AssertEven,
+    AssertOdd,
+    MsbIndex,
+    Select,
+    Cswap,
+    Blend,
+    AddChecked,
+    AddChain,
+    SaturatingAdd,
+    SubConst(u64),
+    NextPow2,
+    Neg,
+    Clear,
+    EqConst(u64),
+    Disjoint,
+    ComposeBits,
+    PermuteBits(&'static [usize]),
+    WindowedAnd(usize),
+    NaiveAnd,
+    /// `compose(verify_decompose(a))`.
+    DecomposeCompose,
+    Lt,
+    LtSigned,
+    MinMaxSigned,
+}
+
+/// A circuit that loads its inputs as private words, runs a single
+/// instruction over them and exposes the outputs from instance row 0 on.
+#[cfg(test)]
+struct InstructionCircuit<const WORD_BITS: u32> {
+    instruction: Instruction,
+    inputs: Vec<Option<Fp>>,
+}
+
+#[cfg(test)]
+impl<const WORD_BITS: u32> Circuit<Fp> for InstructionCircuit<WORD_BITS> {
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            instruction: self.instruction,
+            inputs: vec![None; self.inputs.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MyCircuit::<Fp, WORD_BITS>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let chip = AndChip::<Fp, WORD_BITS>::construct(config);
+        chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        let inputs = self
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(i, input)| {
+                chip.load_private(layouter.namespace(|| format!("load {}", i)), *input)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let layouter_ns = layouter.namespace(|| format!("{:?}", self.instruction));
+        let outputs = match self.instruction {
+            Instruction::And => {
+                vec![chip.and(layouter_ns, inputs[0].clone(), inputs[1].clone())?]
+            }
+            Instruction::AndAll => vec![chip.and_all(layouter_ns, &inputs)?],
+            Instruction::AndFold => {
+                let mut layouter = layouter_ns;
+                let mut inputs = inputs.into_iter();
+                let seed = chip.and_seed(
+                    layouter.namespace(|| "seed"),
+                    inputs.next().expect("a seed"),
+                )?;
+                let acc = inputs.enumerate().try_fold(seed, |acc, (i, word)| {
+                    chip.and_accumulate(layouter.namespace(|| format!("word {}", i)), acc, word)
+                })?;
+                vec![chip.and_finish(layouter.namespace(|| "finish"), acc)?]
+            }
+            Instruction::ColumnAnd => vec![chip.column_and(layouter_ns, &inputs)?],
+            Instruction::XorFold => vec![chip.xor_fold(layouter_ns, &inputs)?],
+            Instruction::Not => {
+                let mut layouter = layouter_ns;
+                let once = chip.not(layouter.namespace(|| "not"), inputs[0].clone())?;
+                let twice = chip.not(layouter.namespace(|| "not not"), once.clone())?;
+                vec![once, twice]
+            }
+            Instruction::Xor => {
+                vec![chip.xor(layouter_ns, inputs[0].clone(), inputs[1].clone())?]
+            }
+            Instruction::Rotl(n) => vec![chip.rotl(layouter_ns, inputs[0].clone(), n)?],
+            Instruction::AndRotated(k) => {
+                vec![chip.and_rotated(layouter_ns, inputs[0].clone(), k)?]
+            }
+            Instruction::Morton => {
+                vec![chip.morton(layouter_ns, inputs[0].clone(), inputs[1].clone())?]
+            }
+            Instruction::Bits(lo, hi) => vec![chip.bits(layouter_ns, inputs[0].clone(), lo, hi)?],
+            Instruction::SetBit => vec![chip.set_bit(
+                layouter_ns,
+                inputs[0].clone(),
+                inputs[1].clone(),
+                inputs[2].clone(),
+            )?],
+            Instruction::Shr(n) => vec![chip.shr(layouter_ns, inputs[0].clone(), n)?],
+            Instruction::ShlVar => {
+                vec![chip.shl_var(layouter_ns, inputs[0].clone(), inputs[1].clone())?]
+            }
+            Instruction::ModPow2(bits) => {
+                vec![chip.mod_pow2(layouter_ns, inputs[0].clone(), bits)?]
+            }
+            Instruction::ToGray => vec![chip.to_gray(layouter_ns, inputs[0].clone())?],
+            Instruction::FromGray => vec![chip.from_gray(layouter_ns, inputs[0].clone())?],
+            Instruction::GrayRoundTrip => {
+                let mut layouter = layouter_ns;
+                let g = chip.to_gray(layouter.namespace(|| "to gray"), inputs[0].clone())?;
+                vec![chip.from_gray(layouter.namespace(|| "from gray"), g)?]
+            }
+            Instruction::ZeroExtend(from_bits) => {
+                vec![chip.zero_extend(layouter_ns, inputs[0].clone(), from_bits)?]
+            }
+            Instruction::SignExtend(from_bits) => {
+                vec![chip.sign_extend(layouter_ns, inputs[0].clone(), from_bits)?]
+            }
+            Instruction::ThetaLane => vec![chip.theta_lane(
+                layouter_ns,
+                inputs[0].clone(),
+                inputs[1].clone(),
+                inputs[2].clone(),
+            )?],
+            Instruction::Majority => vec![chip.majority(
+                layouter_ns,
+                inputs[0].clone(),
+                inputs[1].clone(),
+                inputs[2].clone(),
+            )?],
+            Instruction::Parity => vec![chip.parity(layouter_ns, inputs[0].clone())?],
+            Instruction::AssertEven => {
+                chip.assert_even(layouter_ns, inputs[0].clone())?;
+                vec![]
+            }
+            Instruction::AssertOdd => {
+                chip.assert_odd(layouter_ns, inputs[0].clone())?;
+                vec![]
+            }
+            Instruction::Popcount => vec![chip.popcount(layouter_ns, inputs[0].clone())?],
+            Instruction::CountRuns => vec![chip.count_runs(layouter_ns, inputs[0].clone())?],
+            Instruction::Hamming => {
+                vec![chip.hamming(layouter_ns, inputs[0].clone(), inputs[1].clone())?]
+            }
+            Instruction::MsbIndex => vec![chip.msb_index(layouter_ns, inputs[0].clone())?],
+            Instruction::Select => vec![chip.select(
+                layouter_ns,
+                inputs[0].clone(),
+                inputs[1].clone(),
+                inputs[2].clone(),
+            )?],
+            Instruction::Cswap => {
+                let (first, second) = chip.cswap(
+                    layouter_ns,
+                    inputs[0].clone(),
+                    inputs[1].clone(),
+                    inputs[2].clone(),
+                )?;
+                vec![first, second]
+            }
+            Instruction::Blend => vec![chip.blend(
+                layouter_ns,
+                inputs[0].clone(),
+                inputs[1].clone(),
+                inputs[2].clone(),
+            )?],
+            Instruction::AddChecked => {
+                let (sum, carry) =
+                    chip.add_checked(layouter_ns, inputs[0].clone(), inputs[1].clone())?;
+                vec![sum, carry]
+            }
+            Instruction::AddChain => {
+                let (sum, carry) = chip.add_chain(layouter_ns, &inputs)?;
+                vec![sum, carry]
+            }
+            Instruction::ComposeBits => vec![chip.compose_bits(layouter_ns, &inputs)?],
+            Instruction::PermuteBits(perm) => {
+                vec![chip.permute_bits(layouter_ns, inputs[0].clone(), perm)?]
+            }
+            Instruction::NaiveAnd => {
+                vec![chip.naive_and(layouter_ns, inputs[0].clone(), inputs[1].clone())?]
+            }
+            Instruction::WindowedAnd(window) => chip.windowed_and(layouter_ns, &inputs, window)?,
+            Instruction::DecomposeCompose => {
+                let mut layouter = layouter_ns;
+                let (even, odd) =
+                    chip.verify_decompose(layouter.namespace(|| "decompose"), inputs[0].clone())?;
+                vec![chip.compose(layouter.namespace(|| "compose"), even, odd)?]
+            }
+            Instruction::Lt => vec![chip.lt(layouter_ns, inputs[0].clone(), inputs[1].clone())?],
+            Instruction::LtSigned => {
+                vec![chip.lt_signed(layouter_ns, inputs[0].clone(), inputs[1].clone())?]
+            }
+            Instruction::MinMaxSigned => {
+                let (min, max) =
+                    chip.minmax_signed(layouter_ns, inputs[0].clone(), inputs[1].clone())?;
+                vec![min, max]
+            }
+            Instruction::EqConst(c) => vec![chip.eq_const(layouter_ns, inputs[0].clone(), c)?],
+            Instruction::Disjoint => {
+                vec![chip.disjoint(layouter_ns, inputs[0].clone(), inputs[1].clone())?]
+            }
+            Instruction::SaturatingAdd => {
+                vec![chip.saturating_add(layouter_ns, inputs[0].clone(), inputs[1].clone())?]
+            }
+            Instruction::NextPow2 => vec![chip.next_pow2(layouter_ns, inputs[0].clone())?],
+            Instruction::SubConst(c) => {
+                let (diff, underflow) = chip.sub_const(layouter_ns, inputs[0].clone(), c)?;
+                vec![diff, underflow]
+            }
+            Instruction::Neg => vec![chip.neg(layouter_ns, inputs[0].clone())?],
+            Instruction::Clear => vec![chip.clear(layouter_ns, inputs[0].clone())?],
+        };
+
+        for (row, output) in outputs.into_iter().enumerate() {
+            chip.expose_public(
+                layouter.namespace(|| format!("expose {}", row)),
+                output,
+                0,
+                row,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `instruction` over `inputs` in the mock prover, with `outputs` as the
+/// public inputs.
+#[cfg(test)]
+fn instruction_test<const WORD_BITS: u32>(
+    instruction: Instruction,
+    inputs: &[u64],
+    outputs: &[u64],
+) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+    // Deep enough for the table and every composite instruction at 8 bits.
+    let k = 8;
+    let circuit = InstructionCircuit::<WORD_BITS> {
+        instruction,
+        inputs: inputs.iter().map(|x| Some(Fp::from(*x))).collect(),
+    };
+    let public_inputs = outputs.iter().map(|x| Fp::from(*x)).collect();
+
+    MockProver::run(k, &circuit, vec![public_inputs])
+        .unwrap()
+        .verify()
+}
+
+#[test]
+fn and_accumulate_test() {
+    for words in [
+        vec![0xFF, 0x0F, 0x03],
+        vec![0xA5, 0xFF, 0x3C],
+        vec![0xFF, 0x80],
+        vec![0x7F],
+    ] {
+        let reduced = words.iter().fold(0xFF, |acc, word| acc & word);
+        assert_eq!(
+            instruction_test::<8>(Instruction::AndFold, &words, &[reduced]),
+            Ok(()),
+            "{:x?}",
+            words
+        );
+    }
+    assert_eq!(
+        instruction_test::<8>(Instruction::AndFold, &[0xFF, 0x0F, 0x03], &[0x03]),
+        Ok(())
+    );
+    assert!(instruction_test::<8>(Instruction::AndFold, &[0xFF, 0x0F, 0x03], &[0x0F]).is_err());
+
+    // The accumulator stays in spread lanes, saving a compose and a
+    // decomposition on every word past the second over chained `and`s.
+    let trace = |instruction, words: &[u64]| {
+        let circuit = InstructionCircuit::<8> {
+            instruction,
+            inputs: words.iter().map(|x| Some(Fp::from(*x))).collect(),
+        };
+        SynthesisTrace::of(&circuit).unwrap().advice_rows
+    };
+    for (words, saved) in [
+        (&[0xFF, 0x0F][..], 0),
+        (&[0xFF, 0x0F, 0x03], 4),
+        (&[0xFF, 0x0F, 0x03, 0x01], 8),
+    ] {
+        assert_eq!(
+            trace(Instruction::AndAll, words) - trace(Instruction::AndFold, words),
+            saved,
+            "{:x?}",
+            words
+        );
+    }
+}
+
+#[test]
+fn permute_bits_test() {
+    const IDENTITY: &[usize] = &[0, 1, 2, 3, 4, 5, 6, 7];
+    const REVERSE: &[usize] = &[7, 6, 5, 4, 3, 2, 1, 0];
+    for a in 0..=u8::MAX {
+        assert_eq!(
+            instruction_test::<8>(Instruction::PermuteBits(IDENTITY), &[a as u64], &[a as u64]),
+            Ok(())
+        );
+        assert_eq!(
+            instruction_test::<8>(
+                Instruction::PermuteBits(REVERSE),
+                &[a as u64],
+                &[a.reverse_bits() as u64]
+            ),
+            Ok(()),
+            "reverse({:#04x})",
+            a
+        );
+    }
+    // Bit 0 moves to the top and everything else down one: a rotation.
+    assert_eq!(
+        instruction_test::<8>(
+            Instruction::PermuteBits(&[1, 2, 3, 4, 5, 6, 7, 0]),
+            &[0x01],
+            &[0x80]
+        ),
+        Ok(())
+    );
+
+    for perm in [
+        &[0, 1, 2, 3, 4, 5, 6][..],
+        &[0, 1, 2, 3, 4, 5, 6, 7, 0],
+        &[0, 1, 2, 3, 4, 5, 6, 6],
+        &[0, 1, 2, 3, 4, 5, 6, 8],
+    ] {
+        let circuit = InstructionCircuit::<8> {
+            instruction: Instruction::PermuteBits(perm),
+            inputs: vec![Some(Fp::from(0xA5))],
+        };
+        assert!(SynthesisTrace::of(&circuit).is_err(), "{:?}", perm);
+    }
+}
+
+#[test]
+fn column_and_test() {
+    let matrix = [0b1011_0110u64, 0b1111_0010, 0b0011_1110];
+    let reference = |rows: &[u64]| {
+        (0..8)
+            .filter(|j| rows.iter().all(|row| (row >> j) & 1 == 1))
+            .fold(0, |acc, j| acc | 1 << j)
+    };
+    assert_eq!(reference(&matrix), 0b0011_0010);
+
+    for rows in [&matrix[..], &matrix[..2], &matrix[..1], &[0xFF, 0xFF, 0xFF]] {
+        assert_eq!(
+            instruction_test::<8>(Instruction::ColumnAnd, rows, &[reference(rows)]),
+            Ok(()),
+            "{:x?}",
+            rows
+        );
+    }
+    // The AND of the first two rows only, and their OR.
+    assert!(instruction_test::<8>(Instruction::ColumnAnd, &matrix, &[0b1011_0010]).is_err());
+    assert!(instruction_test::<8>(Instruction::ColumnAnd, &matrix, &[0b1111_1110]).is_err());
+
+    // One fewer compose and decomposition than `and` for every row past the
+    // second.
+    let trace = |instruction| {
+        let circuit = InstructionCircuit::<8> {
+            instruction,
+            inputs: matrix.iter().map(|x| Some(Fp::from(*x))).collect(),
+        };
+        SynthesisTrace::of(&circuit).unwrap().advice_rows
+    };
+    assert_eq!(
+        trace(Instruction::AndAll) - trace(Instruction::ColumnAnd),
+        4
+    );
+    assert_eq!(trace(Instruction::AndFold), trace(Instruction::ColumnAnd));
+}
+
+#[test]
+fn xor_fold_test() {
+    let checksum = [0xAAu64, 0x55, 0xFF];
+    let reference = |words: &[u64]| words.iter().fold(0, |acc, word| acc ^ word);
+    assert_eq!(reference(&checksum), 0);
+
+    for words in [
+        &checksum[..],
+        &checksum[..2],
+        &checksum[..1],
+        &[],
+        &[0x12, 0x34, 0x56, 0x78, 0x9A],
+    ] {
+        assert_eq!(
+            instruction_test::<8>(Instruction::XorFold, words, &[reference(words)]),
+            Ok(()),
+            "{:x?}",
+            words
+        );
+    }
+    // The OR of the words, and their XOR without the last one.
+    assert!(instruction_test::<8>(Instruction::XorFold, &checksum, &[0xFF]).is_err());
+    assert!(instruction_test::<8>(Instruction::XorFold, &checksum, &[0xAA ^ 0x55]).is_err());
+
+    // The same decompositions and adds as `column_and`, only keeping the
+    // other half of each sum's lanes.
+    let trace = |instruction| {
+        let circuit = InstructionCircuit::<8> {
+            instruction,
+            inputs: checksum.iter().map(|x| Some(Fp::from(*x))).collect(),
+        };
+        SynthesisTrace::of(&circuit).unwrap().advice_rows
+    };
+    assert_eq!(trace(Instruction::XorFold), trace(Instruction::ColumnAnd));
+}
+
+#[test]
+fn not_test() {
+    for x in 0..=u8::MAX {
+        assert_eq!(
+            instruction_test::<8>(Instruction::Not, &[x as u64], &[!x as u64, x as u64]),
+            Ok(()),
+            "not({})",
+            x
+        );
+    }
+    // The complement at the wrong width.
+    assert!(instruction_test::<8>(Instruction::Not, &[0x0F], &[0xFFF0, 0x0F]).is_err());
+    assert_eq!(
+        instruction_test::<4>(Instruction::Not, &[0b0110], &[0b1001, 0b0110]),
+        Ok(())
+    );
+}
+
+#[test]
+fn windowed_and_test() {
+    let reference = |words: &[u64], window: usize| -> Vec<u64> {
+        words
+            .windows(window)
+            .map(|run| run.iter().fold(0xFF, |acc, word| acc & word))
+            .collect()
+    };
+
+    let words = [0xFF, 0x0F, 0x03, 0x01];
+    assert_eq!(reference(&words, 2), [0x0F, 0x03, 0x01]);
+    for window in 1..=words.len() {
+        assert_eq!(
+            instruction_test::<8>(
+                Instruction::WindowedAnd(window),
+                &words,
+                &reference(&words, window)
+            ),
+            Ok(()),
+            "window {}",
+            window
+        );
+    }
+    assert!(
+        instruction_test::<8>(Instruction::WindowedAnd(2), &words, &[0x0F, 0x03, 0x03]).is_err()
+    );
+
+    // Long enough for windows to straddle blocks.
+    let words = [0xFE, 0xFD, 0xFB, 0xF7, 0xEF, 0xDF, 0xBF];
+    assert_eq!(
+        instruction_test::<8>(Instruction::WindowedAnd(5), &words, &reference(&words, 5)),
+        Ok(())
+    );
+
+    for window in [0, 5] {
+        let circuit = InstructionCircuit::<8> {
+            instruction: Instruction::WindowedAnd(window),
+            inputs: [0xFF, 0x0F, 0x03, 0x01]
+                .iter()
+                .map(|x| Some(Fp::from(*x)))
+                .collect(),
+        };
+        assert!(SynthesisTrace::of(&circuit).is_err(), "window {}", window);
+    }
+}
+
+#[test]
+fn and_rotated_test() {
+    for k in 0..=8 {
+        for a in 0..=u8::MAX {
+            let c = (a & a.rotate_left(k)) as u64;
+            assert_eq!(
+                instruction_test::<8>(Instruction::AndRotated(k), &[a as u64], &[c]),
+                Ok(()),
+                "{:#04x} & rotl({:#04x}, {})",
+                a,
+                a,
+                k
+            );
+        }
+    }
+    assert!(instruction_test::<8>(Instruction::AndRotated(1), &[0x81], &[0x81]).is_err());
+    // `a & a` still range-checks `a`.
+    assert!(instruction_test::<8>(Instruction::AndRotated(8), &[0x100], &[0x100]).is_err());
+}
+
+#[test]
+fn rotl_test() {
+    for n in 0..=8 {
+        for a in [0u8, 1, 0x80, 0xA5, 0xFF] {
+            let c = a.rotate_left(n) as u64;
+            assert_eq!(
+                instruction_test::<8>(Instruction::Rotl(n), &[a as u64], &[c]),
+                Ok(())
+            );
+        }
+    }
+    assert!(instruction_test::<8>(Instruction::Rotl(1), &[0x81], &[0x81]).is_err());
+}
+
+#[test]
+fn morton_test() {
+    let reference = |x: u64, y: u64| {
+        (0..4).fold(0, |acc, i| {
+            acc | ((x >> i) & 1) << (2 * i) | ((y >> i) & 1) << (2 * i + 1)
+        })
+    };
+    assert_eq!(reference(0b1111, 0), 0b0101_0101);
+    assert_eq!(reference(0b0011, 0b0101), 0b0010_0111);
+
+    for x in 0..16 {
+        for y in 0..16 {
+            let code = reference(x, y);
+            assert_eq!(
+                instruction_test::<4>(Instruction::Morton, &[x, y], &[code]),
+                Ok(()),
+                "morton({:#x}, {:#x})",
+                x,
+                y
+            );
+
+            // The code's even and odd lanes are the spread words.
+            let lanes = (Fp::from(spread(x as u8, 4)), Fp::from(spread(y as u8, 4)));
+            assert_eq!(decompose(Fp::from(code)), lanes);
+        }
+    }
+    assert!(instruction_test::<4>(Instruction::Morton, &[1, 2], &[0b0110]).is_err());
+}
+
+#[test]
+fn bits_test() {
+    for (lo, hi) in [(0, 0), (3, 3), (0, 8), (0, 4), (4, 8), (2, 5), (7, 8)] {
+        for a in 0..=u8::MAX as u64 {
+            let field = (a >> lo) & ((1 << (hi - lo)) - 1);
+            assert_eq!(
+                instruction_test::<8>(Instruction::Bits(lo, hi), &[a], &[field]),
+                Ok(()),
+                "bits {}..{} of {:#04x}",
+                lo,
+                hi,
+                a
+            );
+        }
+    }
+    // Bits 4..8 of 0xA5, but without the shift.
+    assert!(instruction_test::<8>(Instruction::Bits(4, 8), &[0xA5], &[0xA0]).is_err());
+}
+
+#[test]
+fn set_bit_test() {
+    for a in 0..16u64 {
+        for index in 0..4 {
+            for value in 0..2 {
+                let out = (a & !(1 << index)) | (value << index);
+                assert_eq!(
+                    instruction_test::<4>(Instruction::SetBit, &[a, index, value], &[out]),
+                    Ok(()),
+                    "set bit {} of {:#06b} to {}",
+                    index,
+                    a,
+                    value
+                );
+            }
+        }
+    }
+
+    // An index past the word matches no flag, and a non-boolean value isn't a bit.
+    assert!(instruction_test::<4>(Instruction::SetBit, &[0b1010, 4, 1], &[0b1010]).is_err());
+    assert!(instruction_test::<4>(Instruction::SetBit, &[0b1010, 0, 2], &[0b1100]).is_err());
+}
+
+#[test]
+fn mod_pow2_test() {
+    for bits in 0..=8 {
+        for a in 0..=u8::MAX as u64 {
+            assert_eq!(
+                instruction_test::<8>(Instruction::ModPow2(bits), &[a], &[a % (1 << bits)]),
+                Ok(()),
+                "{:#04x} mod 2^{}",
+                a,
+                bits
+            );
+        }
+    }
+    for a in [0, 0x80, 0xFF] {
+        assert_eq!(
+            instruction_test::<8>(Instruction::ModPow2(9), &[a], &[a]),
+            Ok(())
+        );
+    }
+    assert!(instruction_test::<8>(Instruction::ModPow2(4), &[0xA5], &[0xA5]).is_err());
+    assert!(instruction_test::<8>(Instruction::ModPow2(8), &[0x100], &[0x100]).is_err());
+}
+
+#[test]
+fn shr_test() {
+    for n in 0..=9 {
+        for a in [0u64, 1, 0x80, 0xA5, 0xFF] {
+            let c = a.checked_shr(n).unwrap_or(0);
+            assert_eq!(
+                instruction_test::<8>(Instruction::Shr(n), &[a], &[c]),
+                Ok(())
+            );
+        }
+    }
+}
+
+#[test]
+fn shl_var_test() {
+    for shift in 0..4 {
+        for a in 0..16u8 {
+            let c = (a.wrapping_shl(shift) & 0xF) as u64;
+            assert_eq!(
+                instruction_test::<4>(Instruction::ShlVar, &[a as u64, shift as u64], &[c]),
+                Ok(()),
+                "{:#x} << {}",
+                a,
+                shift
+            );
+        }
+    }
+    for shift in 0..8 {
+        for a in (0..=u8::MAX).step_by(7).chain([0x80, 0xFF]) {
+            let c = a.wrapping_shl(shift) as u64;
+            assert_eq!(
+                instruction_test::<8>(Instruction::ShlVar, &[a as u64, shift as u64], &[c]),
+                Ok(()),
+                "{:#04x} << {}",
+                a,
+                shift
+            );
+        }
+    }
+    assert!(instruction_test::<8>(Instruction::ShlVar, &[0xA5, 3], &[0x528]).is_err());
+
+    // A shift of `WORD_BITS` or more would clear the word, but is rejected.
+    assert!(instruction_test::<8>(Instruction::ShlVar, &[0xA5, 8], &[0]).is_err());
+    assert!(instruction_test::<8>(Instruction::ShlVar, &[0xA5, 9], &[0]).is_err());
+    // At 12 bits the four stages could shift by up to 15.
+    assert_eq!(
+        instruction_test::<12>(Instruction::ShlVar, &[0xABD, 11], &[0x800]),
+        Ok(())
+    );
+    assert!(instruction_test::<12>(Instruction::ShlVar, &[0xABC, 12], &[0]).is_err());
+}
+
+#[test]
+fn gray_test() {
+    for a in 0..=u8::MAX as u64 {
+        let gray = a ^ (a >> 1);
+        assert_eq!(
+            instruction_test::<8>(Instruction::ToGray, &[a], &[gray]),
+            Ok(()),
+            "to_gray({:#04x})",
+            a
+        );
+        assert_eq!(
+            instruction_test::<8>(Instruction::FromGray, &[gray], &[a]),
+            Ok(()),
+            "from_gray({:#04x})",
+            gray
+        );
+        assert_eq!(
+            instruction_test::<8>(Instruction::GrayRoundTrip, &[a], &[a]),
+            Ok(()),
+            "from_gray(to_gray({:#04x}))",
+            a
+        );
+    }
+    assert!(instruction_test::<8>(Instruction::ToGray, &[0b0110], &[0b0110]).is_err());
+}
+
+#[test]
+fn extend_test() {
+    assert_eq!(
+        instruction_test::<8>(Instruction::ZeroExtend(4), &[0b1001], &[0b0000_1001]),
+        Ok(())
+    );
+    assert_eq!(
+        instruction_test::<8>(Instruction::SignExtend(4), &[0b1001], &[0b1111_1001]),
+        Ok(())
+    );
+    assert_eq!(
+        instruction_test::<8>(Instruction::SignExtend(4), &[0b0101], &[0b0000_0101]),
+        Ok(())
+    );
+    assert!(instruction_test::<8>(Instruction::SignExtend(4), &[0b1001], &[0b0000_1001]).is_err());
+
+    // 0x19 doesn't fit in 4 bits, whatever the output.
+    for out in [0x19, 0x09] {
+        assert!(instruction_test::<8>(Instruction::ZeroExtend(4), &[0x19], &[out]).is_err());
+        assert!(instruction_test::<8>(Instruction::SignExtend(4), &[0x19], &[out]).is_err());
+    }
+}
+
+#[test]
+fn majority_test() {
+    let maj = |a: u8, b: u8, c: u8| (a & b) | (b & c) | (a & c);
+
+    for a in 0..16 {
+        for b in 0..16 {
+            for c in 0..16 {
+                assert_eq!(
+                    instruction_test::<4>(
+                        Instruction::Majority,
+                        &[a, b, c],
+                        &[maj(a as u8, b as u8, c as u8) as u64]
+                    ),
+                    Ok(()),
+                    "maj({}, {}, {})",
+                    a,
+                    b,
+                    c
+                );
+            }
+        }
+    }
+    // The XOR of the three, i.e. the low bits of the lane sums.
+    assert!(
+        instruction_test::<4>(Instruction::Majority, &[0b0111, 0b1011, 0b1101], &[0b0001]).is_err()
+    );
+    assert_eq!(
+        instruction_test::<8>(Instruction::Majority, &[0xF0, 0xCC, 0xAA], &[0xE8]),
+        Ok(())
+    );
+}
+
+#[test]
+fn clear_test() {
+    for a in 0..=u8::MAX as u64 {
+        assert_eq!(
+            instruction_test::<8>(Instruction::Clear, &[a], &[0]),
+            Ok(()),
+            "clear({:#04x})",
+            a
+        );
+        for wrong in [1, a, 0xFF] {
+            if wrong != 0 {
+                assert!(instruction_test::<8>(Instruction::Clear, &[a], &[wrong]).is_err());
+            }
+        }
+    }
+}
+
+#[test]
+fn neg_test() {
+    assert_eq!(instruction_test::<8>(Instruction::Neg, &[0], &[0]), Ok(()));
+    assert_eq!(
+        instruction_test::<8>(Instruction::Neg, &[1], &[0xFF]),
+        Ok(())
+    );
+    for a in 0..=u8::MAX {
+        let neg = a.wrapping_neg() as u64;
+        assert_eq!(
+            instruction_test::<8>(Instruction::Neg, &[a as u64], &[neg]),
+            Ok(()),
+            "neg({:#04x})",
+            a
+        );
+    }
+    // Without the wrap, `neg(0)` would be `2^WORD_BITS`.
+    assert!(instruction_test::<8>(Instruction::Neg, &[0], &[0x100]).is_err());
+
+    // A `not` and its range check, the constant 1, and the `add carry`
+    // region with the one range check of the sum: the operands aren't
+    // checked again.
+    let circuit = InstructionCircuit::<8> {
+        instruction: Instruction::Neg,
+        inputs: vec![Some(Fp::from(0xA5))],
+    };
+    let trace = SynthesisTrace::of(&circuit).unwrap();
+    assert_eq!(trace.region_histogram().get("decompose"), Some(&2));
+}
+
+#[test]
+fn msb_index_test() {
+    assert_eq!(
+        instruction_test::<8>(Instruction::MsbIndex, &[0], &[0]),
+        Ok(())
+    );
+    for a in 1..=u8::MAX {
+        let index = (7 - a.leading_zeros()) as u64;
+        assert_eq!(
+            instruction_test::<8>(Instruction::MsbIndex, &[a as u64], &[index]),
+            Ok(()),
+            "msb_index({:#04x})",
+            a
+        );
+    }
+    // The lowest set bit doesn't count.
+    assert!(instruction_test::<8>(Instruction::MsbIndex, &[0b0110], &[1]).is_err());
+}
+
+#[test]
+fn parity_test() {
+    for a in 0..=u8::MAX {
+        let parity = (a.count_ones() & 1) as u64;
+        assert_eq!(
+            instruction_test::<8>(Instruction::Parity, &[a as u64], &[parity]),
+            Ok(()),
+            "parity({:#04x})",
+            a
+        );
+        assert!(instruction_test::<8>(Instruction::Parity, &[a as u64], &[1 - parity]).is_err());
+    }
+}
+
+#[test]
+fn assert_even_odd_test() {
+    for a in 0..=u8::MAX {
+        let (holds, fails) = if a % 2 == 0 {
+            (Instruction::AssertEven, Instruction::AssertOdd)
+        } else {
+            (Instruction::AssertOdd, Instruction::AssertEven)
+        };
+        assert_eq!(
+            instruction_test::<8>(holds, &[a as u64], &[]),
+            Ok(()),
+            "{:?}({:#04x})",
+            holds,
+            a
+        );
+        assert!(
+            instruction_test::<8>(fails, &[a as u64], &[]).is_err(),
+            "{:?}({:#04x})",
+            fails,
+            a
+        );
+    }
+
+    // Out of range, even though the low bit is right.
+    assert!(instruction_test::<8>(Instruction::AssertEven, &[0x100], &[]).is_err());
+}
+
+#[test]
+fn popcount_test() {
+    for a in 0..=u8::MAX {
+        let count = a.count_ones() as u64;
+        assert_eq!(
+            instruction_test::<8>(Instruction::Popcount, &[a as u64], &[count]),
+            Ok(()),
+            "popcount({:#04x})",
+            a
+        );
+    }
+    assert!(instruction_test::<8>(Instruction::Popcount, &[0xFF], &[7]).is_err());
+}
+
+#[test]
+fn count_runs_test() {
+    let runs = |a: u8| {
+        let mut runs = 0;
+        let mut previous = false;
+        for i in 0..8 {
+            let bit = (a >> i) & 1 == 1;
+            if bit && !previous {
+                runs += 1;
+            }
+            previous = bit;
+        }
+        runs
+    };
+    assert_eq!(runs(0b1101_0011), 3);
+
+    for a in 0..=u8::MAX {
+        assert_eq!(
+            instruction_test::<8>(Instruction::CountRuns, &[a as u64], &[runs(a)]),
+            Ok(()),
+            "count_runs({:#010b})",
+            a
+        );
+    }
+    // The popcount, and the count of runs of zeros.
+    assert!(instruction_test::<8>(Instruction::CountRuns, &[0b1101_0011], &[5]).is_err());
+    assert!(instruction_test::<8>(Instruction::CountRuns, &[0b1101_0011], &[2]).is_err());
+}
+
+#[cfg(test)]
+fn hamming_case(a: u8, b: u8) {
+    let distance = (a ^ b).count_ones() as u64;
+    assert_eq!(
+        instruction_test::<8>(Instruction::Hamming, &[a as u64, b as u64], &[distance]),
+        Ok(()),
+        "hamming({:#04x}, {:#04x})",
+        a,
+        b
+    );
+}
+
+#[test]
+fn hamming_test() {
+    for a in (0..=u8::MAX).step_by(15) {
+        for b in (0..=u8::MAX).step_by(17) {
+            hamming_case(a, b);
+        }
+    }
+    assert!(instruction_test::<8>(Instruction::Hamming, &[0xF0, 0x0F], &[0]).is_err());
+}
+
+// Every 8-bit pair, at a MockProver run each. Run with
+// `cargo test -- --ignored`.
+#[test]
+#[ignore]
+fn hamming_exhaustive_test() {
+    for a in 0..=u8::MAX {
+        for b in 0..=u8::MAX {
+            hamming_case(a, b);
+        }
+    }
+}
+
+#[cfg(test)]
+fn disjoint_case(a: u8, b: u8) {
+    let disjoint = (a & b == 0) as u64;
+    assert_eq!(
+        instruction_test::<8>(Instruction::Disjoint, &[a as u64, b as u64], &[disjoint]),
+        Ok(()),
+        "disjoint({:#04x}, {:#04x})",
+        a,
+        b
+    );
+}
+
+#[test]
+fn disjoint_test() {
+    for a in (0..=u8::MAX).step_by(15) {
+        for b in (0..=u8::MAX).step_by(17) {
+            disjoint_case(a, b);
+        }
+    }
+    for (a, b) in [(0xF0, 0x0F), (0x00, 0xFF), (0xAA, 0x55), (0x80, 0x01)] {
+        disjoint_case(a, b);
+    }
+
+    // Overlapping allocations give 0, however little they overlap.
+    for (a, b) in [(0xF0, 0x1F), (0xFF, 0xFF), (0x81, 0x01)] {
+        disjoint_case(a, b);
+        assert!(instruction_test::<8>(Instruction::Disjoint, &[a as u64, b as u64], &[1]).is_err());
+    }
+    assert!(instruction_test::<8>(Instruction::Disjoint, &[0xF0, 0x0F], &[0]).is_err());
+    // A word that doesn't fit is rejected, rather than found disjoint.
+    assert!(instruction_test::<8>(Instruction::Disjoint, &[0x100, 0x00], &[1]).is_err());
+}
+
+// Every 8-bit pair, at a MockProver run each. Run with
+// `cargo test -- --ignored`.
+#[test]
+#[ignore]
+fn disjoint_exhaustive_test() {
+    for a in 0..=u8::MAX {
+        for b in 0..=u8::MAX {
+            disjoint_case(a, b);
+        }
+    }
+}
+
+#[test]
+fn cswap_test() {
+    assert_eq!(
+        instruction_test::<8>(Instruction::Cswap, &[0, 3, 5], &[3, 5]),
+        Ok(())
+    );
+    assert_eq!(
+        instruction_test::<8>(Instruction::Cswap, &[1, 3, 5], &[5, 3]),
+        Ok(())
+    );
+    assert!(instruction_test::<8>(Instruction::Cswap, &[1, 3, 5], &[3, 5]).is_err());
+
+    // The outputs are what the two muxes compute for `cond = 2`, so only the
+    // boolean check on `cond` can reject this.
+    assert!(instruction_test::<8>(Instruction::Cswap, &[2, 3, 5], &[7, 1]).is_err());
+}
+
+#[test]
+fn select_test() {
+    assert_eq!(
+        instruction_test::<8>(Instruction::Select, &[0, 3, 5], &[3]),
+        Ok(())
+    );
+    assert_eq!(
+        instruction_test::<8>(Instruction::Select, &[1, 3, 5], &[5]),
+        Ok(())
+    );
+    assert!(instruction_test::<8>(Instruction::Select, &[2, 3, 5], &[7]).is_err());
+}
+
+#[test]
+fn blend_test() {
+    let blend = |mask: u64, a: u64, b: u64| (a & mask) | (b & !mask & 0xF);
+
+    for mask in 0..16 {
+        for a in 0..16 {
+            for b in 0..16 {
+                assert_eq!(
+                    instruction_test::<4>(Instruction::Blend, &[mask, a, b], &[blend(mask, a, b)]),
+                    Ok(()),
+                    "blend({:04b}, {:04b}, {:04b})",
+                    mask,
+                    a,
+                    b
+                );
+            }
+        }
+    }
+    // `select`'s convention, picking `b` where the mask is set.
+    assert!(
+        instruction_test::<4>(Instruction::Blend, &[0b0011, 0b0101, 0b1010], &[0b0110]).is_err()
+    );
+    assert_eq!(
+        instruction_test::<8>(Instruction::Blend, &[0xF0, 0xAB, 0xCD], &[0xAD]),
+        Ok(())
+    );
+
+    // Two more adds and four more decompositions than `majority`, which sums
+    // all three operands' lanes at once and decomposes the sums only once.
+    let trace = |instruction| {
+        let circuit = InstructionCircuit::<8> {
+            instruction,
+            inputs: [0xF0, 0xAB, 0xCD]
+                .iter()
+                .map(|x| Some(Fp::from(*x)))
+                .collect(),
+        };
+        SynthesisTrace::of(&circuit).unwrap().advice_rows
+    };
+    assert_eq!(
+        trace(Instruction::Blend) - trace(Instruction::Majority),
+        35 - 23
+    );
+}
+
+#[test]
+fn next_pow2_test() {
+    for a in 0..=u8::MAX {
+        // 256 doesn't fit, and wraps to 0.
+        let pow2 = a.checked_next_power_of_two().unwrap_or(0) as u64;
+        assert_eq!(
+            instruction_test::<8>(Instruction::NextPow2, &[a as u64], &[pow2]),
+            Ok(()),
+            "next_pow2({})",
+            a
+        );
+    }
+    for (a, pow2) in [
+        (0, 1),
+        (1, 1),
+        (2, 2),
+        (3, 4),
+        (128, 128),
+        (129, 0),
+        (255, 0),
+    ] {
+        assert_eq!(
+            instruction_test::<8>(Instruction::NextPow2, &[a], &[pow2]),
+            Ok(())
+        );
+    }
+
+    // Not the power below, nor the 256 that overflows, nor 0 for 0.
+    assert!(instruction_test::<8>(Instruction::NextPow2, &[5], &[4]).is_err());
+    assert!(instruction_test::<8>(Instruction::NextPow2, &[129], &[256]).is_err());
+    assert!(instruction_test::<8>(Instruction::NextPow2, &[0], &[0]).is_err());
+    assert!(instruction_test::<8>(Instruction::NextPow2, &[0x100], &[0]).is_err());
+}
+
+#[test]
+fn sub_const_test() {
+    for c in [0u8, 1, 0x80, 0xFF] {
+        for a in 0..=u8::MAX {
+            let (diff, underflow) = a.overflowing_sub(c);
+            assert_eq!(
+                instruction_test::<8>(
+                    Instruction::SubConst(c as u64),
+                    &[a as u64],
+                    &[diff as u64, underflow as u64]
+                ),
+                Ok(()),
+                "{:#04x} - {:#04x}",
+                a,
+                c
+            );
+        }
+    }
+
+    // The wrapped difference without the underflow, or the other way round.
+    assert!(instruction_test::<8>(Instruction::SubConst(4), &[3], &[0xFF, 0]).is_err());
+    assert!(instruction_test::<8>(Instruction::SubConst(4), &[5], &[1, 1]).is_err());
+    assert!(instruction_test::<8>(Instruction::SubConst(0), &[0x100], &[0x100, 0]).is_err());
+}
+
+#[test]
+fn saturating_add_test() {
+    for (a, b) in [(0u8, 0u8), (3, 4), (200, 55), (200, 56), (255, 255)] {
+        assert_eq!(
+            instruction_test::<8>(
+                Instruction::SaturatingAdd,
+                &[a as u64, b as u64],
+                &[a.saturating_add(b) as u64]
+            ),
+            Ok(()),
+            "{} + {}",
+            a,
+            b
+        );
+    }
+    // Passing the wrapped sum through must not verify.
+    assert!(instruction_test::<8>(Instruction::SaturatingAdd, &[200, 56], &[0]).is_err());
+    // 300 doesn't fit in a byte, so it can't pass for a carry of 1 and a sum
+    // of 44.
+    assert!(instruction_test::<8>(Instruction::SaturatingAdd, &[300, 0], &[255]).is_err());
+    assert!(instruction_test::<8>(Instruction::AddChecked, &[300, 0], &[44, 1]).is_err());
+}
+
+#[test]
+fn compose_bits_test() {
+    let byte = 0b0110_1001u64;
+    let bits: Vec<_> = (0..8).map(|i| (byte >> i) & 1).collect();
+    assert_eq!(
+        instruction_test::<8>(Instruction::ComposeBits, &bits, &[byte]),
+        Ok(())
+    );
+
+    // 2 at bit 0 weighs the same as 1 at bit 1, but isn't a bit.
+    let mut bits = vec![0; 8];
+    bits[0] = 2;
+    assert!(instruction_test::<8>(Instruction::ComposeBits, &bits, &[2]).is_err());
+}
+
+#[test]
+fn signed_test() {
+    let byte = |x: i8| x as u8 as u64;
+
+    // AND doesn't care about the sign: -1 is all ones.
+    assert_eq!(
+        instruction_test::<8>(Instruction::And, &[byte(-1), byte(5)], &[5]),
+        Ok(())
+    );
+
+    // -1 < 1 as signed words, but 0xFF > 1 as unsigned ones.
+    assert_eq!(
+        instruction_test::<8>(Instruction::LtSigned, &[byte(-1), byte(1)], &[1]),
+        Ok(())
+    );
+    assert_eq!(
+        instruction_test::<8>(Instruction::Lt, &[byte(-1), byte(1)], &[0]),
+        Ok(())
+    );
+    assert!(instruction_test::<8>(Instruction::LtSigned, &[byte(-1), byte(1)], &[0]).is_err());
+    // `2^8 + 5` isn't a byte, and doesn't compare as 5.
+    assert!(instruction_test::<8>(Instruction::LtSigned, &[0x105, 6], &[1]).is_err());
+    assert!(instruction_test::<8>(Instruction::LtSigned, &[6, 0x105], &[0]).is_err());
+    assert!(instruction_test::<8>(Instruction::MinMaxSigned, &[0x105, 6], &[0x105, 6]).is_err());
+
+    assert_eq!(
+        instruction_test::<8>(
+            Instruction::MinMaxSigned,
+            &[byte(1), byte(-128)],
+            &[byte(-128), byte(1)]
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn all_8_bit_words_lt_test() {
+    let words = (any::<u8>(), any::<u8>());
+    run_proptest(ProptestConfig::with_cases(50), words, |(a, b)| {
+        prop_assert_eq!(
+            instruction_test::<8>(Instruction::Lt, &[a as u64, b as u64], &[(a < b) as u64]),
+            Ok(())
+        );
+        Ok(())
+    });
+}
+
+#[test]
+fn all_8_bit_words_lt_signed_test() {
+    let words = (any::<i8>(), any::<i8>());
+    run_proptest(ProptestConfig::with_cases(50), words, |(a, b)| {
+        prop_assert_eq!(
+            instruction_test::<8>(
+                Instruction::LtSigned,
+                &[a as u8 as u64, b as u8 as u64],
+                &[(a < b) as u64]
+            ),
+            Ok(())
+        );
+        Ok(())
+    });
+}
+
+#[test]
+fn eq_const_test() {
+    for c in [0, 5, 0xFF, 0x100] {
+        for a in 0..=u8::MAX as u64 {
+            let eq = (a == c) as u64;
+            assert_eq!(
+                instruction_test::<8>(Instruction::EqConst(c), &[a], &[eq]),
+                Ok(()),
+                "{} == {}",
+                a,
+                c
+            );
+            assert!(instruction_test::<8>(Instruction::EqConst(c), &[a], &[1 - eq]).is_err());
+        }
+    }
+}
+
+#[test]
+fn add_chain_test() {
+    for words in [
+        vec![0xFF, 0xFF, 0x02],
+        vec![0x7F],
+        vec![0x80, 0x80],
+        vec![0xFF; 4],
+        vec![0x01, 0x02, 0x03, 0x04],
+    ] {
+        let total: u32 = words.iter().map(|&w| w as u32).sum();
+        let (low, carry) = ((total & 0xFF) as u64, (total >> 8) as u64);
+        assert_eq!(
+            instruction_test::<8>(Instruction::AddChain, &words, &[low, carry]),
+            Ok(()),
+            "{:x?}",
+            words
+        );
+    }
+    assert_eq!(
+        instruction_test::<8>(Instruction::AddChain, &[0xFF, 0xFF, 0x02], &[0x00, 2]),
+        Ok(())
+    );
+    assert!(instruction_test::<8>(Instruction::AddChain, &[0xFF, 0xFF, 0x02], &[0x00, 1]).is_err());
+    assert!(
+        instruction_test::<8>(Instruction::AddChain, &[0xFF, 0xFF, 0x02], &[0x200, 0]).is_err()
+    );
+}
+
+#[test]
+fn add_checked_test() {
+    assert_eq!(
+        instruction_test::<8>(Instruction::AddChecked, &[200, 55], &[255, 0]),
+        Ok(())
+    );
+    assert_eq!(
+        instruction_test::<8>(Instruction::AddChecked, &[200, 56], &[0, 1]),
+        Ok(())
+    );
+    assert!(instruction_test::<8>(Instruction::AddChecked, &[200, 56], &[256, 0]).is_err());
+}
+
+#[test]
+fn all_8_bit_words_xor_test() {
+    let words = (0..2u64.pow(8), 0..2u64.pow(8));
+    run_proptest(ProptestConfig::with_cases(50), words, |(a, b)| {
+        prop_assert_eq!(
+            instruction_test::<8>(Instruction::Xor, &[a, b], &[a ^ b]),
+            Ok(())
+        );
+        prop_assert!(instruction_test::<8>(Instruction::Xor, &[a, b], &[a ^ b ^ 1]).is_err());
+        Ok(())
+    });
+}
+
+#[test]
+fn all_8_bit_words_saturating_add_test() {
+    let words = (any::<u8>(), any::<u8>());
+    run_proptest(ProptestConfig::with_cases(50), words, |(a, b)| {
+        prop_assert_eq!(
+            instruction_test::<8>(
+                Instruction::SaturatingAdd,
+                &[a as u64, b as u64],
+                &[a.saturating_add(b) as u64]
+            ),
+            Ok(())
+        );
+        Ok(())
+    });
+}
+
+#[test]
+fn all_8_bit_words_theta_lane_test() {
+    let words = (any::<u8>(), any::<u8>(), any::<u8>());
+    run_proptest(ProptestConfig::with_cases(50), words, |(a, b, c)| {
+        let lane = a ^ b.rotate_left(1) ^ c;
+        prop_assert_eq!(
+            instruction_test::<8>(
+                Instruction::ThetaLane,
+                &[a as u64, b as u64, c as u64],
+                &[lane as u64]
+            ),
+            Ok(())
+        );
+        Ok(())
+    });
+}
+
+/// A lone `add` region whose output cell holds `out` rather than `a + b`.
+#[cfg(test)]
+#[derive(Clone, Copy)]
+struct TamperedAddCircuit {
+    a: Fp,
+    b: Fp,
+    out: Fp,
+}
+
+#[cfg(test)]
+impl Circuit<Fp> for TamperedAddCircuit {
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        *self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MyCircuit::<Fp, 8>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let chip = AndChip::<Fp, 8>::construct(config.clone());
+        chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        let a = chip.load_private(layouter.namespace(|| "load a"), Some(self.a))?;
+        let b = chip.load_private(layouter.namespace(|| "load b"), Some(self.b))?;
+
+        // `NumericInstructions::add`, but with the output witness overridden.
+        layouter.assign_region(
+            || "add",
+            |mut region: Region<'_, Fp>| {
+                config.s_add.enable(&mut region, 0)?;
+                a.0.copy_advice(|| "lhs", &mut region, config.advice[0], 0)?;
+                b.0.copy_advice(|| "rhs", &mut region, config.advice[1], 0)?;
+                region.assign_advice(|| "lhs + rhs", config.advice[0], 1, || Ok(self.out))?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+fn tampered_add_test(a: Fp, b: Fp, out: Fp) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+    MockProver::run(5, &TamperedAddCircuit { a, b, out }, vec![vec![]])
+        .unwrap()
+        .verify()
+}
+
+#[test]
+fn add_gate_binds_output_test() {
+    let (a, b) = (Fp::from(0b0101), Fp::from(0b0100));
+    assert_eq!(tampered_add_test(a, b, a + b), Ok(()));
+
+    for out in [
+        a + b + Fp::one(),
+        a + b - Fp::one(),
+        a * b,
+        Fp::zero(),
+        -Fp::one(),
+    ] {
+        let failures = tampered_add_test(a, b, out).unwrap_err();
+        assert!(
+            failures
+                .iter()
+                .all(|failure| failure.to_string().contains("('add')")),
+            "unexpected failures for out = {:?}: {:?}",
+            out,
+            failures
+        );
+    }
+}
+
+/// The AND pipeline of `MyCircuit` with the split of `e = ae + be` witnessed
+/// as `e_lanes` instead of computed by `decompose`.
+///
+/// With `LOOKUPS = false` every decomposition is laid out under the `compose`
+/// gate, which enforces the same `lhs + 2 * rhs = out` as the `decompose`
+/// gate but none of the `even_bits` lookups: the lanes are not range-checked.
+#[cfg(test)]
+#[derive(Clone, Copy)]
+struct TamperedAndCircuit<const LOOKUPS: bool> {
+    a: u64,
+    b: u64,
+    e_lanes: Option<(u64, u64)>,
+}
+
+/// `MyCircuit` minus its range checks.
+#[cfg(test)]
+type UnsoundAndCircuit = TamperedAndCircuit<false>;
+
+#[cfg(test)]
+impl<const LOOKUPS: bool> TamperedAndCircuit<LOOKUPS> {
+    fn decompose(
+        &self,
+        config: &AndConfig,
+        mut layouter: impl Layouter<Fp>,
+        c: &Word<Fp>,
+        (even, odd): (Fp, Fp),
+    ) -> Result<(Word<Fp>, Word<Fp>), Error> {
+        layouter.assign_region(
+            || "decompose",
+            |mut region: Region<'_, Fp>| {
+                if LOOKUPS {
+                    config.s_decompose.enable(&mut region, 0)?;
+                } else {
+                    config.s_compose.enable(&mut region, 0)?;
+                }
+
+                let e = region.assign_advice(|| "even bits", config.advice[0], 0, || Ok(even))?;
+                let o = region.assign_advice(|| "odd bits", config.advice[1], 0, || Ok(odd))?;
+                c.0.copy_advice(|| "out", &mut region, config.advice[0], 1)?;
+                Ok((Word(e), Word(o)))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+impl<const LOOKUPS: bool> Circuit<Fp> for TamperedAndCircuit<LOOKUPS> {
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        *self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MyCircuit::<Fp, 8>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let chip = AndChip::<Fp, 8>::construct(config.clone());
+        chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        let honest = |c: &Word<Fp>| c.0.value().copied().map(decompose).ok_or(Error::Synthesis);
+
+        let a = chip.load_private(layouter.namespace(|| "load a"), Some(Fp::from(self.a)))?;
+        let b = chip.load_private(layouter.namespace(|| "load b"), Some(Fp::from(self.b)))?;
+
+        let (ae, ao) = self.decompose(
+            &config,
+            layouter.namespace(|| "a decomposition"),
+            &a,
+            honest(&a)?,
+        )?;
+        let (be, bo) = self.decompose(
+            &config,
+            layouter.namespace(|| "b decomposition"),
+            &b,
+            honest(&b)?,
+        )?;
+
+        let e = chip.add(layouter.namespace(|| "ae + be"), ae, be)?;
+        let o = chip.add(layouter.namespace(|| "ao + bo"), ao, bo)?;
+
+        let e_lanes = match self.e_lanes {
+            Some((even, odd)) => (Fp::from(even), Fp::from(odd)),
+            None => honest(&e)?,
+        };
+        let (_, eo) = self.decompose(
+            &config,
+            layouter.namespace(|| "e decomposition"),
+            &e,
+            e_lanes,
+        )?;
+        let (_, oo) = self.decompose(
+            &config,
+            layouter.namespace(|| "o decomposition"),
+            &o,
+            honest(&o)?,
+        )?;
+
+        let a_and_b = chip.compose(layouter.namespace(|| "compose eo and oo"), eo, oo)?;
+        chip.expose_public(layouter.namespace(|| "expose a_and_b"), a_and_b, 0, 0)
+    }
+}
+
+/// What the `even_bits` lookups buy: without them, `3 & 4` can be "proven"
+/// to be 1.
+#[test]
+fn unsound_and_circuit_test() {
+    // `e = ae + be = 5` honestly splits into `5 + 2 * 0`, which gives the
+    // correct `3 & 4 = 0`. Splitting it into `3 + 2 * 1` instead satisfies the
+    // decompose gate just as well, but 3 isn't a spread value.
+    let bogus_lanes = Some((3, 1));
+    let bogus_and = vec![Fp::one()];
+
+    let unsound = UnsoundAndCircuit {
+        a: 3,
+        b: 4,
+        e_lanes: bogus_lanes,
+    };
+    let prover = MockProver::run(5, &unsound, vec![bogus_and.clone()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let sound = TamperedAndCircuit::<true> {
+        a: 3,
+        b: 4,
+        e_lanes: bogus_lanes,
+    };
+    let prover = MockProver::run(5, &sound, vec![bogus_and.clone()]).unwrap();
+    assert!(prover.verify().is_err());
+
+    let circuit = MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(3)),
+        b: Some(Fp::from(4)),
+        output_row: 0,
+    };
+    let prover = MockProver::run(5, &circuit, vec![bogus_and]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+/// The odd lane is looked up as it is stored, shifted down a bit. That is
+/// the form the table holds: shifted down, a word's odd bits land on the
+/// even positions of a spread value, and the `2` in the `decompose` gate
+/// shifts them back. An unshifted lane wouldn't satisfy the gate at all.
+#[test]
+fn odd_lane_lookup_test() {
+    use halo2_proofs::dev::VerifyFailure;
+
+    // `e = 5` also splits into `1 + 2 * 2`, with a spread even lane. Only the
+    // odd lane, 2, isn't one, and the AND comes out as `compose(2, 0) = 2`.
+    let bogus_lanes = Some((1, 2));
+    let bogus_and = vec![Fp::from(2)];
+
+    let unsound = UnsoundAndCircuit {
+        a: 3,
+        b: 4,
+        e_lanes: bogus_lanes,
+    };
+    let prover = MockProver::run(5, &unsound, vec![bogus_and.clone()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let sound = TamperedAndCircuit::<true> {
+        a: 3,
+        b: 4,
+        e_lanes: bogus_lanes,
+    };
+    let prover = MockProver::run(5, &sound, vec![bogus_and]).unwrap();
+    let failures = prover.verify().unwrap_err();
+    assert!(!failures.is_empty());
+    assert!(
+        failures.iter().all(|failure| matches!(
+            failure,
+            VerifyFailure::Lookup {
+                lookup_index: 1,
+                ..
+            }
+        )),
+        "{:?}",
+        failures
+    );
+}
+
+/// The object-safe part of `Layouter<F>`, so that `ChipCallCircuit` can hand
+/// its closure one layouter type whatever the floor planner and assignment
+/// underneath.
+#[cfg(test)]
+trait ErasedLayouter<F: Field> {
+    fn assign_region(
+        &mut self,
+        name: String,
+        assignment: &mut dyn FnMut(Region<'_, F>) -> Result<(), Error>,
+    ) -> Result<(), Error>;
+
+    fn assign_table(
+        &mut self,
+        name: String,
+        assignment: &mut dyn FnMut(halo2_proofs::circuit::Table<'_, F>) -> Result<(), Error>,
+    ) -> Result<(), Error>;
+
+    fn constrain_instance(
+        &mut self,
+        cell: halo2_proofs::circuit::Cell,
+        column: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error>;
+
+    fn push_namespace(&mut self, name: String);
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>);
+}
+
+#[cfg(test)]
+impl<F: Field, L: Layouter<F>> ErasedLayouter<F> for L {
+    fn assign_region(
+        &mut self,
+        name: String,
+        assignment: &mut dyn FnMut(Region<'_, F>) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        Layouter::assign_region(self, || name.clone(), assignment)
+    }
+
+    fn assign_table(
+        &mut self,
+        name: String,
+        assignment: &mut dyn FnMut(halo2_proofs::circuit::Table<'_, F>) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        Layouter::assign_table(self, || name.clone(), assignment)
+    }
+
+    fn constrain_instance(
+        &mut self,
+        cell: halo2_proofs::circuit::Cell,
+        column: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error> {
+        Layouter::constrain_instance(self, cell, column, row)
+    }
+
+    fn push_namespace(&mut self, name: String) {
+        Layouter::push_namespace(self, || name)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        Layouter::pop_namespace(self, gadget_name)
+    }
+}
+
+/// The layouter `ChipCallCircuit` passes its closure.
+#[cfg(test)]
+struct DynLayouter<'a, F: Field>(&'a mut dyn ErasedLayouter<F>);
+
+#[cfg(test)]
+impl<F: Field> Layouter<F> for DynLayouter<'_, F> {
+    type Root = Self;
+
+    fn assign_region<A, AR, N, NR>(&mut self, name: N, mut assignment: A) -> Result<AR, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        // The floor planner may run the assignment more than once; the last
+        // run is the one that counts.
+        let mut result = None;
+        self.0.assign_region(name().into(), &mut |region| {
+            result = Some(assignment(region)?);
+            Ok(())
+        })?;
+        Ok(result.expect("the region was assigned"))
+    }
+
+    fn assign_table<A, N, NR>(&mut self, name: N, mut assignment: A) -> Result<(), Error>
+    where
+        A: FnMut(halo2_proofs::circuit::Table<'_, F>) -> Result<(), Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.0.assign_table(name().into(), &mut assignment)
+    }
+
+    fn constrain_instance(
+        &mut self,
+        cell: halo2_proofs::circuit::Cell,
+        column: Column<Instance>,
+        row: usize,
+    ) -> Result<(), Error> {
+        self.0.constrain_instance(cell, column, row)
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.0.push_namespace(name_fn().into())
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.0.pop_namespace(gadget_name)
+    }
+}
+
+/// Runs one closure against an 8-bit `AndChip` over `MyCircuit`'s columns,
+/// for tests of a single chip call. Whatever the test wants to look at
+/// afterwards, the closure stashes in a `Cell` or `RefCell` it captures.
+#[cfg(test)]
+struct ChipCallCircuit<F, C>(C, PhantomData<F>);
+
+#[cfg(test)]
+fn chip_call<F, C>(call: C) -> ChipCallCircuit<F, C>
+where
+    F: FieldExt,
+    C: Fn(&AndChip<F, 8>, &mut DynLayouter<'_, F>) -> Result<(), Error> + Clone,
+{
+    ChipCallCircuit(call, PhantomData)
+}
+
+#[cfg(test)]
+impl<F, C> Circuit<F> for ChipCallCircuit<F, C>
+where
+    F: FieldExt,
+    C: Fn(&AndChip<F, 8>, &mut DynLayouter<'_, F>) -> Result<(), Error> + Clone,
+{
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.clone(), PhantomData)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        MyCircuit::<F, 8>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = AndChip::<F, 8>::construct(config);
+        (self.0)(&chip, &mut DynLayouter(&mut layouter))
+    }
+}
+
+/// What `Word::value_u64` makes of `value` loaded into a word.
+#[cfg(test)]
+fn value_u64_of(value: Fp) -> Option<u64> {
+    let value_u64 = Cell::new(None);
+    SynthesisTrace::of(&chip_call(|chip, layouter| {
+        let word = chip.load_private(layouter.namespace(|| "load"), Some(value))?;
+        value_u64.set(word.value_u64::<8>());
+        Ok(())
+    }))
+    .unwrap();
+    value_u64.get()
+}
+
+#[test]
+fn word_value_eq_test() {
+    use std::collections::HashSet;
+
+    let words = std::cell::RefCell::new(Vec::new());
+    SynthesisTrace::of(&chip_call(|chip, layouter| {
+        for (i, value) in [3, 3, 4].into_iter().enumerate() {
+            let word = chip.load_private(
+                layouter.namespace(|| format!("load {}", i)),
+                Some(Fp::from(value)),
+            )?;
+            words.borrow_mut().push(word);
+        }
+        Ok(())
+    }))
+    .unwrap();
+    let words = words.take();
+
+    // Different cells, same value.
+    assert_eq!(words[0], words[1]);
+    assert_eq!(words[0].value_key(), words[1].value_key());
+    assert_ne!(words[0], words[2]);
+    assert_ne!(words[0].value_key(), words[2].value_key());
+
+    let distinct: HashSet<_> = words.iter().filter_map(Word::value_key).collect();
+    assert_eq!(distinct.len(), 2);
+}
+
+#[test]
+fn load_private_checked_test() {
+    let result = &std::cell::RefCell::new(None);
+    let circuit = |value: u64| {
+        chip_call(move |chip, layouter| {
+            chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+            let loaded =
+                chip.load_private_checked(layouter.namespace(|| "load"), Some(Fp::from(value)));
+            *result.borrow_mut() = Some(loaded.map(|_| ()));
+            Ok(())
+        })
+    };
+
+    let prover = MockProver::run(5, &circuit(255), vec![vec![]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+    assert!(matches!(result.take(), Some(Ok(()))));
+
+    SynthesisTrace::of(&circuit(256)).unwrap();
+    let error = result.take().unwrap().unwrap_err();
+    assert!(matches!(
+        &error,
+        AndError::OutOfRange { value, word_bits: 8 } if *value == Fp::from(256).to_repr()
+    ));
+    assert_eq!(
+        error.to_string(),
+        format!("0x{}100 does not fit in 8 bits", "0".repeat(61))
+    );
+}
+
+#[test]
+fn value_u64_out_of_range_test() {
+    assert_eq!(value_u64_of(Fp::from(255)), Some(255));
+    assert_eq!(value_u64_of(Fp::from(256)), None);
+    assert_eq!(value_u64_of(Fp::from_u128(1 << 64)), None);
+    assert_eq!(value_u64_of(-Fp::one()), None);
+}
+
+#[test]
+fn value_u64_test() {
+    run_proptest(ProptestConfig::default(), any::<u8>(), |a| {
+        prop_assert_eq!(value_u64_of(Fp::from(a as u64)), Some(a as u64));
+        Ok(())
+    });
+}
+
+#[test]
+fn double_table_allocation_test() {
+    let second_alloc = Cell::new(None);
+    SynthesisTrace::of(&chip_call(|chip, layouter| {
+        chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+        second_alloc.set(Some(
+            chip.alloc_table(&mut layouter.namespace(|| "alloc table again")),
+        ));
+        Ok(())
+    }))
+    .unwrap();
+
+    assert!(matches!(
+        second_alloc.take(),
+        Some(Err(AndError::TableAlreadyAllocated))
+    ));
+}
+
+// TODO move into test module
+// It's used in the proptests
+#[cfg(test)]
+fn gen_proof_and_verify<const WORD_BITS: u32>(a: u64, b: u64, c: u64) {
+    let circuit: MyCircuit<Fp, WORD_BITS> = MyCircuit {
+        a: Some(Fp::from(a)),
+        b: Some(Fp::from(b)),
+        output_row: 0,
+    };
+    let k = circuit.required_k();
+
+    let c = Fp::from(c);
+
+    // Arrange the public input. We expose the bitwise AND result in row 0
+    // of the instance column, so we position it there in our public inputs.
+
+    use halo2_proofs::{
+        plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, SingleVerifier},
+        poly::commitment::Params,
+        transcript::{Blake2bRead, Blake2bWrite},
+    };
+    use pasta_curves::{vesta, EqAffine};
+    use rand_core::OsRng;
+
+    let params: Params<EqAffine> = halo2_proofs::poly::commitment::Params::new(k);
+    let vk = keygen_vk(&params, &circuit).unwrap();
+
+    let pk = keygen_pk(&params, vk, &circuit).unwrap();
+    let mut transcript = Blake2bWrite::<_, vesta::Affine, _>::init(vec![]);
+
+    create_proof(
+        &params,
+        &pk,
+        &[circuit],
+        &[&[&[c]]],
+        &mut OsRng,
+        &mut transcript,
+    )
+    .expect("Failed to create proof");
+
+    let proof = transcript.finalize();
+
+    let mut transcript = Blake2bRead::init(&proof[..]);
+
+    verify_proof(
+        &params,
+        pk.get_vk(),
+        SingleVerifier::new(&params),
+        &[&[&[c]]],
+        &mut transcript,
+    )
+    .expect("could not verify_proof")
+}
+
+/// Errors reported by the host-side helpers around the circuit, as opposed to
+/// the `plonk::Error`s raised while synthesizing or proving it.
+#[derive(Debug)]
+pub enum AndError {
+    /// The circuit constrains `expected` instance rows, but `actual` public
+    /// inputs were supplied.
+    InstanceLengthMismatch { expected: usize, actual: usize },
+    /// `alloc_table` was called a second time on the same chip.
+    TableAlreadyAllocated,
+    /// Synthesizing the circuit failed.
+    Synthesis(Error),
+    /// A value does not fit in a `word_bits`-bit word. `value` is its
+    /// canonical little-endian representation, `PrimeField::to_repr`, which
+    /// keeps the error the same whatever field the chip is over.
+    OutOfRange { value: Vec<u8>, word_bits: u32 },
+    /// A proof failed to verify.
+    Verification(Error),
+    /// `AndChip::permute_bits` was given something other than a permutation
+    /// of the word's bit positions.
+    InvalidPermutation(Vec<usize>),
+    /// `AndChip::windowed_and` was given a `window` of 0, or longer than the
+    /// `len` words it was given.
+    InvalidWindow { window: usize, len: usize },
+    /// `AndChip::try_construct` was given a config with a column that
+    /// equality isn't enabled on.
+    EqualityNotEnabled(Column<Any>),
+    /// `AndChip::try_construct` was given a constraint system with no
+    /// constants column.
+    NoConstantColumn,
+    /// A witness file could not be read or written.
+    Io(std::io::Error),
+    /// A witness file is malformed; `line` is 1-based, or 0 for the file as
+    /// a whole.
+    Witness { line: usize, message: String },
+}
+
+impl fmt::Display for AndError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AndError::InstanceLengthMismatch { expected, actual } => write!(
+                f,
+                "the circuit exposes {} public inputs, but {} were supplied",
+                expected, actual
+            ),
+            AndError::TableAlreadyAllocated => {
+                write!(f, "the even_bits table has already been allocated")
+            }
+            AndError::Synthesis(e) => write!(f, "synthesis failed: {:?}", e),
+            AndError::Verification(e) => write!(f, "verification failed: {:?}", e),
+            AndError::InvalidPermutation(perm) => {
+                write!(f, "{:?} is not a permutation of the word's bits", perm)
+            }
+            AndError::EqualityNotEnabled(column) => {
+                write!(f, "equality is not enabled on {:?}", column)
+            }
+            AndError::NoConstantColumn => write!(f, "no constants column is enabled"),
+            AndError::InvalidWindow { window, len } => {
+                write!(f, "a window of {} doesn't fit {} words", window, len)
+            }
+            AndError::OutOfRange { value, word_bits } => {
+                write!(f, "0x")?;
+                for byte in value.iter().rev() {
+                    write!(f, "{:02x}", byte)?;
+                }
+                write!(f, " does not fit in {} bits", word_bits)
+            }
+            AndError::Io(e) => write!(f, "witness file: {}", e),
+            AndError::Witness { line: 0, message } => write!(f, "witness file: {}", message),
+            AndError::Witness { line, message } => {
+                write!(f, "witness file, line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AndError {}
+
+impl AndError {
+    /// `OutOfRange` for `value`, in any field.
+    pub fn out_of_range<F: PrimeField>(value: &F, word_bits: u32) -> Self {
+        AndError::OutOfRange {
+            value: value.to_repr().as_ref().to_vec(),
+            word_bits,
+        }
+    }
+}
+
+impl From<Error> for AndError {
+    fn from(e: Error) -> Self {
+        AndError::Synthesis(e)
+    }
+}
+
+impl From<std::io::Error> for AndError {
+    fn from(e: std::io::Error) -> Self {
+        AndError::Io(e)
+    }
+}
+
+/// Lets `synthesize` implementations use `?` on the chip's fallible helpers.
+/// halo2 has no room for our own errors, so anything but a wrapped
+/// `plonk::Error` becomes `Error::Synthesis`.
+impl From<AndError> for Error {
+    fn from(e: AndError) -> Self {
+        match e {
+            AndError::Synthesis(e) => e,
+            _ => Error::Synthesis,
+        }
+    }
+}
+
+/// The inputs to `MyCircuit` and the `c = a & b` it is expected to expose,
+/// as stored in a witness file.
+///
+/// A witness file is a small subset of TOML: one `key = value` per line,
+/// with decimal or `0x`-prefixed hex integer values, blank lines and `#`
+/// comments. `a` and `b` are required. `c` defaults to `a & b`, and giving
+/// it explicitly lets a file record a vector that should fail to verify.
+///
+/// ```toml
+/// # 3 & 4
+/// a = 3
+/// b = 4
+/// c = 0
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitInputs {
+    pub a: u64,
+    pub b: u64,
+    pub c: u64,
+}
+
+impl CircuitInputs {
+    pub fn circuit<const WORD_BITS: u32>(&self) -> MyCircuit<Fp, WORD_BITS> {
+        MyCircuit {
+            a: Some(Fp::from(self.a)),
+            b: Some(Fp::from(self.b)),
+            output_row: 0,
+        }
+    }
+
+    pub fn public_inputs(&self) -> Vec<Vec<Fp>> {
+        vec![vec![Fp::from(self.c)]]
+    }
+}
+
+/// Reads a witness file in the format described on `CircuitInputs`.
+pub fn load_witness(path: &std::path::Path) -> Result<CircuitInputs, AndError> {
+    let (mut a, mut b, mut c) = (None, None, None);
+
+    for (i, line) in std::fs::read_to_string(path)?.lines().enumerate() {
+        let invalid = |message: String| AndError::Witness {
+            line: i + 1,
+            message,
+        };
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("expected `key = value`, got {:?}", line)))?;
+        let value = value.trim();
+        let value = match value.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => value.parse(),
+        }
+        .map_err(|e| invalid(format!("invalid value {:?}: {}", value, e)))?;
+
+        let slot = match key.trim() {
+            "a" => &mut a,
+            "b" => &mut b,
+            "c" => &mut c,
+            key => return Err(invalid(format!("unknown key {:?}", key))),
+        };
+        if slot.replace(value).is_some() {
+            return Err(invalid(format!("duplicate key {:?}", key.trim())));
+        }
+    }
+
+    let missing = |key| AndError::Witness {
+        line: 0,
+        message: format!("missing key {:?}", key),
+    };
+    let (a, b) = (
+        a.ok_or_else(|| missing("a"))?,
+        b.ok_or_else(|| missing("b"))?,
+    );
+    Ok(CircuitInputs {
+        a,
+        b,
+        c: c.unwrap_or(a & b),
+    })
+}
+
+/// Writes `inputs` as a witness file that `load_witness` reads back.
+pub fn save_witness(path: &std::path::Path, inputs: &CircuitInputs) -> Result<(), AndError> {
+    let CircuitInputs { a, b, c } = inputs;
+    std::fs::write(path, format!("a = {}\nb = {}\nc = {}\n", a, b, c))?;
+    Ok(())
+}
+
+#[test]
+fn witness_file_test() {
+    let path = std::env::temp_dir().join(format!("and-witness-{}.toml", std::process::id()));
+
+    let inputs = CircuitInputs { a: 3, b: 4, c: 0 };
+    save_witness(&path, &inputs).unwrap();
+    let loaded = load_witness(&path).unwrap();
+    assert_eq!(loaded, inputs);
+
+    let prover = MockProver::run(5, &loaded.circuit::<8>(), loaded.public_inputs()).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    std::fs::write(&path, "# 0xA5 & 0x3C\n\na = 0xA5\nb = 60  # decimal\n").unwrap();
+    assert_eq!(
+        load_witness(&path).unwrap(),
+        CircuitInputs {
+            a: 0xA5,
+            b: 60,
+            c: 0xA5 & 60
+        }
+    );
+
+    for (contents, line) in [
+        ("a = 3\nb 4\n", 2),
+        ("a = 3\nb = four\n", 2),
+        ("a = 3\nd = 4\n", 2),
+        ("a = 3\na = 4\n", 2),
+        ("a = 3\n", 0),
+    ] {
+        std::fs::write(&path, contents).unwrap();
+        assert!(
+            matches!(load_witness(&path), Err(AndError::Witness { line: l, .. }) if l == line),
+            "{:?}",
+            contents
+        );
+    }
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(load_witness(&path), Err(AndError::Io(_))));
+}
+
+/// A record of what a circuit's `synthesize` does, collected by running it
+/// against this recorder instead of a prover. No constraints are checked.
+#[derive(Debug, Default)]
+pub struct SynthesisTrace {
+    /// `(column index, row)` of every instance cell that a copy constraint
+    /// touches, i.e. every row handed to `expose_public`.
+    pub instance_cells: BTreeSet<(usize, usize)>,
+    /// One past the last advice row assigned: the rows the layout occupies.
+    pub advice_rows: usize,
+    /// `(column index, row)` of every advice assignment, in order.
+    pub advice_cells: Vec<(usize, usize)>,
+    /// Every `(selector, row)` a region enabled.
+    pub selectors: Vec<(Selector, usize)>,
+    /// The name of every region entered, tables included, in order.
+    pub regions: Vec<String>,
+    /// For each of `regions`, the first and last row it assigns an advice
+    /// cell or enables a selector at, if any.
+    pub region_rows: Vec<Option<(usize, usize)>>,
+    /// `(region, column index, row)` of every advice cell assigned when it
+    /// already held a value. halo2 would only report these when proving.
+    pub overwrites: Vec<(String, usize, usize)>,
+    assigned_cells: BTreeSet<(usize, usize)>,
+}
+
+impl SynthesisTrace {
+    /// Configures and synthesizes `circuit`, recording what it assigns.
+    ///
+    /// Cells assigned twice are recorded in `overwrites` rather than
+    /// rejected; it is up to the caller to check that it is empty.
+    pub fn of<C: Circuit<Fp>>(circuit: &C) -> Result<Self, Error> {
+        let mut meta = ConstraintSystem::default();
+        let config = C::configure(&mut meta);
+
+        let mut trace = Self::default();
+        C::FloorPlanner::synthesize(&mut trace, circuit, config, meta.constants().clone())?;
+        Ok(trace)
+    }
+
+    /// The number of rows `selector` is enabled at.
+    pub fn enabled_rows(&self, selector: Selector) -> usize {
+        self.selectors
+            .iter()
+            .filter(|(s, _)| *s == selector)
+            .count()
+    }
+
+    /// Widens the current region's rows to include `row`.
+    fn touch_row(&mut self, row: usize) {
+        if let Some(rows) = self.region_rows.last_mut() {
+            *rows = Some(match *rows {
+                Some((first, last)) => (first.min(row), last.max(row)),
+                None => (row, row),
+            });
+        }
+    }
+
+    /// How many regions were created under each name.
+    pub fn region_histogram(&self) -> HashMap<String, usize> {
+        let mut histogram = HashMap::new();
+        for name in &self.regions {
+            *histogram.entry(name.clone()).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// The number of public inputs the circuit expects: one past the last
+    /// instance row it constrains.
+    pub fn public_input_count(&self) -> usize {
+        self.instance_cells
+            .iter()
+            .map(|(_, row)| row + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl<F: Field> Assignment<F> for SynthesisTrace {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.regions.push(name_fn().into());
+        self.region_rows.push(None);
+    }
+
+    fn exit_region(&mut self) {}
+
+    fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.selectors.push((*selector, row));
+        self.touch_row(row);
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Option<F>, Error> {
+        Ok(None)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.advice_rows = std::cmp::max(self.advice_rows, row + 1);
+        self.advice_cells.push((column.index(), row));
+        self.touch_row(row);
+        if !self.assigned_cells.insert((column.index(), row)) {
+            let region = self.regions.last().cloned().unwrap_or_default();
+            self.overwrites.push((region, column.index(), row));
+        }
+
+        // Evaluate the value so that assigned cells carry it forward, but
+        // don't insist on one: witness-less circuits are traceable too.
+        let _ = to();
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Fixed>,
+        _: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let _ = to();
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        for (column, row) in [(left_column, left_row), (right_column, right_row)] {
+            if *column.column_type() == Any::Instance {
+                self.instance_cells.insert((column.index(), row));
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Option<Assigned<F>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+#[test]
+fn region_histogram_test() {
+    let circuit = MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(3)),
+        b: Some(Fp::from(4)),
+        output_row: 0,
+    };
+    let histogram = SynthesisTrace::of(&circuit).unwrap().region_histogram();
+
+    let expected: HashMap<String, usize> = [
+        ("even bits table", 1),
+        ("load private", 2),
+        ("decompose", 4),
+        ("add", 2),
+        ("compose", 1),
+    ]
+    .iter()
+    .map(|(name, count)| (name.to_string(), *count))
+    .collect();
+    assert_eq!(histogram, expected);
+}
+
+/// Two 8-bit `AndChip`s over the same columns, one prefixed `"lhs"` and
+/// one `"rhs"`, proving `a & b` and `c & d` at instance rows 0 and 1.
+#[cfg(test)]
+#[derive(Default)]
+struct PrefixedChipsCircuit {
+    a: Option<Fp>,
+    b: Option<Fp>,
+    c: Option<Fp>,
+    d: Option<Fp>,
+}
+
+#[cfg(test)]
+impl Circuit<Fp> for PrefixedChipsCircuit {
+    type Config = (AndConfig, AndConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let advice = [meta.advice_column(), meta.advice_column()];
+        let instance = vec![meta.instance_column()];
+        let constant = meta.fixed_column();
+
+        (
+            AndChip::<Fp, 8>::configure(meta, advice, instance.clone(), constant),
+            AndChip::<Fp, 8>::configure(meta, advice, instance, constant),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        (lhs, rhs): Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let lhs = AndChip::<Fp, 8>::construct_with_prefix(lhs, "lhs");
+        let rhs = AndChip::<Fp, 8>::construct_with_prefix(rhs, "rhs");
+
+        for (row, chip, x, y) in [(0, &lhs, self.a, self.b), (1, &rhs, self.c, self.d)] {
+            chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+            let x = chip.load_private(layouter.namespace(|| "load x"), x)?;
+            let y = chip.load_private(layouter.namespace(|| "load y"), y)?;
+            let x_and_y = chip.and(layouter.namespace(|| "x & y"), x, y)?;
+            chip.expose_public(layouter.namespace(|| "expose x & y"), x_and_y, 0, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn prefixed_chips_circuit() -> PrefixedChipsCircuit {
+    PrefixedChipsCircuit {
+        a: Some(Fp::from(0xA5)),
+        b: Some(Fp::from(0x3C)),
+        c: Some(Fp::from(7)),
+        d: Some(Fp::from(6)),
+    }
+}
+
+#[test]
+fn construct_with_prefix_test() {
+    let circuit = prefixed_chips_circuit();
+    let prover =
+        MockProver::run(6, &circuit, vec![vec![Fp::from(0xA5 & 0x3C), Fp::from(6)]]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let histogram = SynthesisTrace::of(&circuit).unwrap().region_histogram();
+    for prefix in ["lhs", "rhs"] {
+        for (name, count) in [
+            ("even bits table", 1),
+            ("load private", 2),
+            ("decompose", 4),
+            ("add", 2),
+            ("compose", 1),
+        ] {
+            let name = format!("{}/{}", prefix, name);
+            assert_eq!(histogram.get(&name), Some(&count), "{}", name);
+        }
+    }
+    assert_eq!(histogram.len(), 10, "{:?}", histogram);
+}
+
+/// The DOT graph of `PrefixedChipsCircuit` tells the two chips' namespaces
+/// apart. Needs `layout-viz`, like `circuit_dot_snapshot_test`.
+#[cfg(feature = "layout-viz")]
+#[test]
+fn prefixed_chips_dot_test() {
+    let dot = halo2_proofs::dev::circuit_dot_graph(&prefixed_chips_circuit());
+    for namespace in [
+        "lhs/spread sums",
+        "rhs/spread sums",
+        "lhs/a decomposition",
+        "rhs/a decomposition",
+        "lhs/compose eo and oo",
+        "rhs/compose eo and oo",
+    ] {
+        let label = format!("label=\"{}\"", namespace);
+        assert!(dot.contains(&label), "{} missing from\n{}", namespace, dot);
+    }
+    assert!(!dot.contains("label=\"spread sums\""), "{}", dot);
+}
+
+/// Lays out `old` and `new` with `SynthesisTrace` and lists how their
+/// regions differ, one line per region removed (`- `) or added (`+ `), in
+/// layout order. Identical layouts give an empty string.
+///
+/// A region is described by its name, its height and the selectors it
+/// enables, at offsets from its first row, so a region moving down because
+/// another was inserted above it doesn't show up. A first line compares the
+/// advice rows used against the `2^k - UNUSABLE_ROWS` available.
+pub fn layout_diff<A: Circuit<Fp>, B: Circuit<Fp>>(old: &A, new: &B, k: u32) -> String {
+    let describe = |trace: SynthesisTrace| -> Vec<String> {
+        let usable = (1 << k) - UNUSABLE_ROWS;
+        let mut lines = vec![format!("{} of {} rows", trace.advice_rows, usable)];
+        for (name, rows) in trace.regions.iter().zip(&trace.region_rows) {
+            lines.push(match rows {
+                Some((first, last)) => {
+                    let selectors: Vec<_> = trace
+                        .selectors
+                        .iter()
+                        .filter(|(_, row)| (first..=last).contains(&row))
+                        .map(|(selector, row)| format!("{:?}@{}", selector, row - first))
+                        .collect();
+                    format!(
+                        "{}: {} rows [{}]",
+                        name,
+                        last - first + 1,
+                        selectors.join(", ")
+                    )
+                }
+                None => format!("{}: no advice", name),
+            });
+        }
+        lines
+    };
+    let (old, new) = match (SynthesisTrace::of(old), SynthesisTrace::of(new)) {
+        (Ok(old), Ok(new)) => (describe(old), describe(new)),
+        (old, new) => {
+            return format!(
+                "failed to lay out: old {:?}, new {:?}",
+                old.err(),
+                new.err()
+            )
+        }
+    };
+
+    // `common[i][j]` is the length of the longest common subsequence of
+    // `old[i..]` and `new[j..]`.
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                std::cmp::max(common[i + 1][j], common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+            diff.push_str(&format!("- {}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+ {}\n", new[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+#[test]
+fn layout_diff_test() {
+    let circuit = || MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(0xA5)),
+        b: Some(Fp::from(0x3C)),
+        output_row: 0,
+    };
+    assert_eq!(layout_diff(&circuit(), &circuit(), 6), "");
+    // Witnesses don't change the layout.
+    assert_eq!(
+        layout_diff(&circuit(), &circuit().without_witnesses(), 6),
+        ""
+    );
+
+    // A third operand loads one more word and runs one more AND.
+    let three = MultiAndCircuit::<8> {
+        operands: vec![
+            Some(Fp::from(0xA5)),
+            Some(Fp::from(0x3C)),
+            Some(Fp::from(0x0F)),
+        ],
+    };
+    let diff = layout_diff(&circuit(), &three, 6);
+    let removed: Vec<_> = diff.lines().filter(|line| line.starts_with("- ")).collect();
+    let added: Vec<_> = diff.lines().filter(|line| line.starts_with("+ ")).collect();
+    assert_eq!(removed.len(), 1, "{}", diff);
+    assert!(removed[0].ends_with("of 58 rows"), "{}", diff);
+    assert_eq!(added.len(), 1 + 1 + 7, "{}", diff);
+    assert!(
+        added
+            .iter()
+            .any(|line| line.starts_with("+ load private: 1 rows")),
+        "{}",
+        diff
+    );
+    assert_eq!(
+        added
+            .iter()
+            .filter(|line| line.starts_with("+ decompose: 2 rows"))
+            .count(),
+        4,
+        "{}",
+        diff
+    );
+}
+
+/// Guards against selector bleed in the `3 & 4` circuit: every gate queries
+/// the row it is enabled at and the next one, so both have to lie inside a
+/// single region, and rows outside any region have every selector off.
+#[test]
+fn selectors_stay_in_regions_test() {
+    let circuit = MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(3)),
+        b: Some(Fp::from(4)),
+        output_row: 0,
+    };
+    let trace = SynthesisTrace::of(&circuit).unwrap();
+    let regions: Vec<_> = trace.region_rows.iter().flatten().collect();
+
+    for (selector, row) in &trace.selectors {
+        assert!(
+            regions
+                .iter()
+                .any(|(first, last)| first <= row && row < last),
+            "{:?} at row {} spills out of its region",
+            selector,
+            row
+        );
+    }
+    for row in 0..trace.advice_rows {
+        if !regions
+            .iter()
+            .any(|(first, last)| (*first..=*last).contains(&row))
+        {
+            assert!(
+                trace.selectors.iter().all(|(_, r)| *r != row),
+                "selector enabled at row {} between regions",
+                row
+            );
+        }
+    }
+
+    let mut meta = ConstraintSystem::default();
+    let config = MyCircuit::<Fp, 8>::configure(&mut meta);
+    for (selector, rows) in [
+        (config.s_decompose, 4),
+        (config.s_add, 2),
+        (config.s_compose, 1),
+        (config.s_bits, 0),
+        (config.s_bool, 0),
+        (config.s_select, 0),
+        (config.s_add_carry, 0),
+        (config.s_is_equal, 0),
+    ] {
+        assert_eq!(trace.enabled_rows(selector), rows, "{:?}", selector);
+    }
+    assert_eq!(trace.selectors.len(), 4 + 2 + 1);
+}
+
+/// `MockProver::verify`, plus the checks on how the circuit was laid out that
+/// it leaves alone, all collected into one report.
+///
+/// `verify` evaluates the constraints: a selector enabled at a row where no
+/// gate reads it constrains nothing and passes, and a gate only sees
+/// unassigned cells as zero. Here the circuit is also traced, and every
+/// enabled selector has to belong to a gate, and every advice cell those
+/// gates query at that row has to have been assigned somewhere. Selectors
+/// that only gate lookups are reported as having no gate.
+#[cfg(test)]
+fn strict_verify<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+    instance: Vec<Vec<Fp>>,
+) -> Result<(), Vec<String>> {
+    let mut report: Vec<String> = match MockProver::run(k, circuit, instance) {
+        Ok(prover) => prover
+            .verify()
+            .err()
+            .unwrap_or_default()
+            .iter()
+            .map(|failure| format!("{:?}", failure))
+            .collect(),
+        Err(e) => vec![format!("synthesis failed: {:?}", e)],
+    };
+
+    let mut meta = ConstraintSystem::default();
+    C::configure(&mut meta);
+    // The selectors each gate is behind, and the `(column, rotation)` of
+    // every advice cell it queries.
+    let gates: Vec<_> = meta
+        .gates()
+        .iter()
+        .map(|gate| {
+            let selectors = std::cell::RefCell::new(Vec::new());
+            let cells = std::cell::RefCell::new(Vec::new());
+            for polynomial in gate.polynomials() {
+                polynomial.evaluate(
+                    &|_| (),
+                    &|selector| selectors.borrow_mut().push(selector),
+                    &|_, _, _| (),
+                    &|_, column, rotation: Rotation| cells.borrow_mut().push((column, rotation.0)),
+                    &|_, _, _| (),
+                    &|_| (),
+                    &|_, _| (),
+                    &|_, _| (),
+                    &|_, _| (),
+                );
+            }
+            (gate.name(), selectors.into_inner(), cells.into_inner())
+        })
+        .collect();
+
+    match SynthesisTrace::of(circuit) {
+        Ok(trace) => {
+            for (selector, row) in &trace.selectors {
+                let mut gated = false;
+                for (name, selectors, cells) in &gates {
+                    if !selectors.contains(selector) {
+                        continue;
+                    }
+                    gated = true;
+                    for (column, rotation) in cells {
+                        let cell_row = *row as i32 + rotation;
+                        if cell_row < 0
+                            || !trace.assigned_cells.contains(&(*column, cell_row as usize))
+                        {
+                            report.push(format!(
+                                "gate {:?} at row {} queries unassigned advice cell \
+                                 (column {}, row {})",
+                                name, row, column, cell_row
+                            ));
+                        }
+                    }
+                }
+                if !gated {
+                    report.push(format!(
+                        "{:?} is enabled at row {} but no gate reads it",
+                        selector, row
+                    ));
+                }
+            }
+        }
+        Err(e) => report.push(format!("tracing failed: {:?}", e)),
+    }
+
+    if report.is_empty() {
+        Ok(())
+    } else {
+        Err(report)
+    }
+}
+
+/// `MyCircuit` with a selector no gate reads enabled on the side: nothing is
+/// constrained wrongly, but the layout has a loose end.
+#[cfg(test)]
+#[derive(Default)]
+struct StraySelectorCircuit(MyCircuit<Fp, 8>);
+
+#[cfg(test)]
+impl Circuit<Fp> for StraySelectorCircuit {
+    type Config = (AndConfig, Selector);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        (MyCircuit::<Fp, 8>::configure(meta), meta.selector())
+    }
+
+    fn synthesize(
+        &self,
+        (config, stray): Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let advice = config.advice[0];
+        self.0.synthesize(config, layouter.namespace(|| "a & b"))?;
+        layouter.assign_region(
+            || "stray",
+            |mut region| {
+                stray.enable(&mut region, 0)?;
+                region.assign_advice(|| "unused", advice, 0, || Ok(Fp::zero()))?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn try_construct_test() {
+    let mut meta = ConstraintSystem::<Fp>::default();
+    let config = MyCircuit::<Fp, 8>::configure(&mut meta);
+    assert!(AndChip::<Fp, 8>::try_construct(&meta, config.clone()).is_ok());
+
+    // The same columns in a constraint system that never enabled anything
+    // on them.
+    let mut bare = ConstraintSystem::<Fp>::default();
+    let advice = [bare.advice_column(), bare.advice_column()];
+    bare.instance_column();
+    bare.fixed_column();
+    assert!(matches!(
+        AndChip::<Fp, 8>::try_construct(&bare, config.clone()),
+        Err(AndError::EqualityNotEnabled(column)) if column == Column::<Any>::from(advice[0])
+    ));
+
+    for column in advice {
+        bare.enable_equality(column);
+    }
+    let error = AndChip::<Fp, 8>::try_construct(&bare, config.clone())
+        .err()
+        .unwrap();
+    assert!(
+        matches!(error, AndError::EqualityNotEnabled(column) if column == Column::<Any>::from(config.instance[0]))
+    );
+    assert!(error.to_string().starts_with("equality is not enabled on"));
+
+    bare.enable_equality(config.instance[0]);
+    assert!(matches!(
+        AndChip::<Fp, 8>::try_construct(&bare, config),
+        Err(AndError::NoConstantColumn)
+    ));
+}
+
+#[test]
+fn strict_verify_test() {
+    let circuit = MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(3)),
+        b: Some(Fp::from(6)),
+        output_row: 0,
+    };
+    assert_eq!(
+        strict_verify(5, &circuit, vec![vec![Fp::from(3 & 6)]]),
+        Ok(())
+    );
+    assert!(strict_verify(5, &circuit, vec![vec![Fp::from(3)]]).is_err());
+
+    let circuit = StraySelectorCircuit(circuit);
+    let public_inputs = vec![vec![Fp::from(3 & 6)]];
+    let prover = MockProver::run(5, &circuit, public_inputs.clone()).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let report = strict_verify(5, &circuit, public_inputs).unwrap_err();
+    assert_eq!(report.len(), 1, "{:#?}", report);
+    assert!(report[0].ends_with("but no gate reads it"), "{}", report[0]);
+}
+
+/// `verify_decompose` puts the even lane at `advice[0]` offset 0 and copies
+/// the word to offset 1 of the same column; no region may reuse a cell.
+#[test]
+fn no_overwrites_test() {
+    let word = |x: u64| Some(Fp::from(x));
+
+    let trace = SynthesisTrace::of(&DecomposeCircuit::<8> { value: word(0xA5) }).unwrap();
+    assert!(trace.overwrites.is_empty(), "{:?}", trace.overwrites);
+    let trace = SynthesisTrace::of(&MyCircuit::<Fp, 8> {
+        a: word(3),
+        b: word(4),
+        output_row: 0,
+    })
+    .unwrap();
+    assert!(trace.overwrites.is_empty(), "{:?}", trace.overwrites);
+
+    for (instruction, inputs) in [
+        (Instruction::Majority, vec![1, 2, 3]),
+        (Instruction::Cswap, vec![1, 2, 3]),
+        (Instruction::Blend, vec![1, 2, 3]),
+        (Instruction::AddChecked, vec![200, 100]),
+        (Instruction::LtSigned, vec![1, 2]),
+        (Instruction::EqConst(5), vec![5]),
+    ] {
+        let circuit = InstructionCircuit::<8> {
+            instruction,
+            inputs: inputs.into_iter().map(word).collect(),
+        };
+        let trace = SynthesisTrace::of(&circuit).unwrap();
+        assert!(trace.overwrites.is_empty(), "{:?}", instruction);
+    }
+}
+
+/// Assigns the same advice cell twice in one region.
+#[cfg(test)]
+#[derive(Default)]
+struct OverwritingCircuit;
+
+#[cfg(test)]
+impl Circuit<Fp> for OverwritingCircuit {
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MyCircuit::<Fp, 8>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "overwrite",
+            |mut region: Region<'_, Fp>| {
+                region.assign_advice(|| "first", config.advice[1], 0, || Ok(Fp::one()))?;
+                region.assign_advice(|| "second", config.advice[1], 0, || Ok(Fp::zero()))?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn overwrite_flagged_test() {
+    let trace = SynthesisTrace::of(&OverwritingCircuit).unwrap();
+    assert_eq!(trace.overwrites, vec![("overwrite".to_string(), 1, 0)]);
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Which advice assignment, counting from 0, `Perturbing` adds 1 to.
+    static PERTURBED_CELL: std::cell::Cell<Option<usize>> = std::cell::Cell::new(None);
+}
+
+/// A floor planner that lays circuits out exactly like `SimpleFloorPlanner`,
+/// but adds 1 to the value of the advice assignment picked by
+/// `PERTURBED_CELL`. The circuit's own view of the value is left untouched,
+/// so every other cell keeps its honest witness.
+#[cfg(test)]
+#[derive(Debug)]
+struct Perturbing;
+
+#[cfg(test)]
+impl FloorPlanner for Perturbing {
+    fn synthesize<F: Field, CS: Assignment<F>, C: Circuit<F>>(
+        cs: &mut CS,
+        circuit: &C,
+        config: C::Config,
+        constants: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        let mut cs = PerturbingAssignment {
+            cs,
+            next: 0,
+            target: PERTURBED_CELL.with(|cell| cell.get()),
+        };
+        SimpleFloorPlanner::synthesize(&mut cs, circuit, config, constants)
+    }
+}
+
+#[cfg(test)]
+struct PerturbingAssignment<'a, CS> {
+    cs: &'a mut CS,
+    next: usize,
+    target: Option<usize>,
+}
+
+#[cfg(test)]
+impl<'a, F: Field, CS: Assignment<F>> Assignment<F> for PerturbingAssignment<'a, CS> {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.enter_region(name_fn)
+    }
+
+    fn exit_region(&mut self) {
+        self.cs.exit_region()
+    }
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        annotation: A,
+        selector: &Selector,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.enable_selector(annotation, selector, row)
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Option<F>, Error> {
+        self.cs.query_instance(column, row)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let perturb = self.target == Some(self.next);
+        self.next += 1;
+
+        self.cs.assign_advice(annotation, column, row, || {
+            let value: Assigned<F> = to()?.into();
+            Ok(if perturb {
+                value + Assigned::from(F::one())
+            } else {
+                value
+            })
+        })
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Result<VR, Error>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.cs.assign_fixed(annotation, column, row, to)
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<Any>,
+        left_row: usize,
+        right_column: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.cs.copy(left_column, left_row, right_column, right_row)
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        row: usize,
+        to: Option<Assigned<F>>,
+    ) -> Result<(), Error> {
+        self.cs.fill_from_row(column, row, to)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.cs.pop_namespace(gadget_name)
+    }
+}
+
+/// Hunts for under-constrained cells: adding 1 to any single advice cell of
+/// the `3 & 4` circuit has to break some constraint.
+#[test]
+fn single_cell_perturbation_test() {
+    let circuit = MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(3)),
+        b: Some(Fp::from(4)),
+        output_row: 0,
+    };
+    let public_inputs = vec![Fp::from(3 & 4)];
+
+    let cells = SynthesisTrace::of(&circuit).unwrap().advice_cells;
+    let circuit = WithFloorPlanner::<_, Perturbing>::new(circuit);
+
+    let prover = MockProver::run(5, &circuit, vec![public_inputs.clone()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    for (target, (column, row)) in cells.into_iter().enumerate() {
+        PERTURBED_CELL.with(|cell| cell.set(Some(target)));
+        let prover = MockProver::run(5, &circuit, vec![public_inputs.clone()]).unwrap();
+        assert!(
+            prover.verify().is_err(),
+            "advice[{}] at row {} + 1 still verifies",
+            column,
+            row
+        );
+    }
+    PERTURBED_CELL.with(|cell| cell.set(None));
+}
+
+/// Counts the lookups `circuit` makes into the `even_bits` table.
+///
+/// Every lookup is gated by `s_decompose`, so each row a decompose region
+/// enables it at costs one lookup per argument registered in `configure`.
+pub fn lookup_count<const WORD_BITS: u32>(
+    circuit: &MyCircuit<Fp, WORD_BITS>,
+) -> Result<usize, Error> {
+    let mut meta = ConstraintSystem::default();
+    let config = MyCircuit::<Fp, WORD_BITS>::configure(&mut meta);
+
+    let rows = SynthesisTrace::of(circuit)?.enabled_rows(config.s_decompose);
+    Ok(rows * meta.lookups().len())
+}
+
+#[test]
+fn lookup_count_test() {
+    let circuit = MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(3)),
+        b: Some(Fp::from(4)),
+        output_row: 0,
+    };
+
+    // Four decompositions, each looking up its even and its odd lane.
+    assert_eq!(lookup_count(&circuit).unwrap(), 4 * 2);
+    assert_eq!(lookup_count(&MyCircuit::<Fp, 8>::default()).unwrap(), 4 * 2);
+}
+
+/// Checks, before handing them to a prover, that `public_inputs` has exactly
+/// as many values as `circuit` exposes.
+///
+/// A mismatch otherwise surfaces as an opaque failure from deep inside
+/// halo2, or not at all when the extra values are simply left unconstrained.
+pub fn validate_public_inputs<C: Circuit<Fp>>(
+    circuit: &C,
+    public_inputs: &[Fp],
+) -> Result<(), AndError> {
+    let expected = SynthesisTrace::of(circuit)?.public_input_count();
+    if expected != public_inputs.len() {
+        return Err(AndError::InstanceLengthMismatch {
+            expected,
+            actual: public_inputs.len(),
+        });
+    }
+    Ok(())
+}
+
+#[test]
+fn validate_public_inputs_test() {
+    let circuit = MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(3)),
+        b: Some(Fp::from(4)),
+        output_row: 0,
+    };
+
+    assert!(validate_public_inputs(&circuit, &[Fp::from(3 & 4)]).is_ok());
+    assert!(matches!(
+        validate_public_inputs(&circuit, &[Fp::from(3 & 4), Fp::from(1)]),
+        Err(AndError::InstanceLengthMismatch {
+            expected: 1,
+            actual: 2
+        })
+    ));
+    assert!(matches!(
+        validate_public_inputs(&circuit, &[]),
+        Err(AndError::InstanceLengthMismatch {
+            expected: 1,
+            actual: 0
+        })
+    ));
+}
+
+/// Commits to the instance column the same way `verify_proof` does before
+/// checking a proof against `public_inputs`.
+///
+/// This only computes the public-input binding; it doesn't look at, let alone
+/// check, any proof. The size of the domain is read off `params`. Like
+/// `verify_proof`, this refuses more public inputs than there are usable
+/// rows, `2^k - UNUSABLE_ROWS` for the chip's layout, with
+/// `Error::InstanceTooLarge`, rather than committing to a column the
+/// verifier never would.
+pub fn instance_commitment(
+    params: &Params<EqAffine>,
+    public_inputs: &[Fp],
+) -> Result<EqAffine, Error> {
+    let n = params.get_g().len();
+    if public_inputs.len() > n - UNUSABLE_ROWS {
+        return Err(Error::InstanceTooLarge);
+    }
+
+    let domain = EvaluationDomain::<Fp>::new(1, n.trailing_zeros());
+    let mut values = public_inputs.to_vec();
+    values.resize(n, Fp::zero());
+
+    Ok(params
+        .commit_lagrange(&domain.lagrange_from_vec(values), Blind::default())
+        .to_affine())
+}
+
+#[test]
+fn instance_commitment_test() {
+    let k = 5;
+    let params: Params<EqAffine> = Params::new(k);
+    let commit = |public_inputs: &[Fp]| instance_commitment(&params, public_inputs).unwrap();
+
+    let commitment = commit(&[Fp::from(3 & 4)]);
+    assert_eq!(commitment, commit(&[Fp::from(3 & 4)]));
+    assert_ne!(commitment, commit(&[Fp::from(3 & 5)]));
+    // Trailing zeros are the padding anyway.
+    assert_eq!(commitment, commit(&[Fp::from(3 & 4), Fp::zero()]));
+
+    let usable = (1 << k) - UNUSABLE_ROWS;
+    assert!(instance_commitment(&params, &vec![Fp::one(); usable]).is_ok());
+    assert!(matches!(
+        instance_commitment(&params, &vec![Fp::one(); usable + 1]),
+        Err(Error::InstanceTooLarge)
+    ));
+}
+
+/// Fingerprints a verifying key by hashing it into a fresh transcript, the
+/// way the prover and verifier bind it before anything else, and squeezing
+/// a single challenge out.
+///
+/// Any change to the constraint system, be it a gate, a lookup or
+/// `WORD_BITS`, changes the fingerprint.
+pub fn vk_fingerprint(vk: &VerifyingKey<EqAffine>) -> [u8; 32] {
+    let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+    vk.hash_into(&mut transcript)
+        .expect("writing to a Vec doesn't fail");
+
+    let challenge: Challenge255<EqAffine> = transcript.squeeze_challenge();
+    challenge.get_scalar().to_repr()
+}
+
+/// Fingerprints the `even_bits` table `alloc_table` loads for
+/// `WORD_BITS`, as laid out by `SpreadTable::Full`, the same way
+/// `vk_fingerprint` does a verifying key: every entry goes into a fresh
+/// transcript as a scalar, in row order, and a single challenge comes out.
+///
+/// The table is fixed, and every range check and AND leans on it, so
+/// `table_digest_test` pins the digest to catch changes to `even_bits_at`.
+/// The pinned value is only for `WORD_BITS = 8`. Pinning another width, or
+/// a deliberate change to the table, means regenerating it: run the test,
+/// and take the new digest from the failing assertion once the new table
+/// has been checked by hand.
+pub fn table_digest<const WORD_BITS: u32>() -> [u8; 32] {
+    let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+    for entry in SpreadTable::Full.entries::<Fp, WORD_BITS>(0) {
+        transcript
+            .common_scalar(entry)
+            .expect("writing to a Vec doesn't fail");
+    }
+
+    let challenge: Challenge255<EqAffine> = transcript.squeeze_challenge();
+    challenge.get_scalar().to_repr()
+}
+
+#[test]
+fn table_digest_test() {
+    const TABLE_DIGEST_8: [u8; 32] = [
+        0xcd, 0x59, 0x5d, 0xf3, 0x1a, 0xc4, 0xfa, 0x88, 0xd1, 0x93, 0x36, 0x06, 0x02, 0x6a, 0x9f,
+        0xc3, 0x82, 0x29, 0xe7, 0x4a, 0x65, 0xe4, 0xc5, 0xf0, 0x56, 0x34, 0xf4, 0xb9, 0xf3, 0x5e,
+        0x7f, 0x26,
+    ];
+    assert_eq!(table_digest::<8>(), TABLE_DIGEST_8);
+
+    // The 4-bit table is a prefix of the 8-bit one, and still differs.
+    assert_ne!(table_digest::<4>(), TABLE_DIGEST_8);
+}
+
+#[test]
+fn vk_fingerprint_test() {
+    use halo2_proofs::plonk::keygen_vk;
+
+    // Large enough for the 16-bit table, so only `WORD_BITS` differs.
+    let k = 9;
+    let params: Params<EqAffine> = Params::new(k);
+    let fingerprint = |vk| vk_fingerprint(&vk);
+
+    let vk_8 = fingerprint(keygen_vk(&params, &MyCircuit::<Fp, 8>::default()).unwrap());
+    let vk_8_again = fingerprint(keygen_vk(&params, &MyCircuit::<Fp, 8>::default()).unwrap());
+    let vk_16 = fingerprint(keygen_vk(&params, &MyCircuit::<Fp, 16>::default()).unwrap());
+
+    assert_eq!(vk_8, vk_8_again);
+    assert_ne!(vk_8, vk_16);
+}
+
+/// A proof of `a & b = c` for `MyCircuit`, along with the public input it
+/// was made against.
+#[derive(Clone, Debug)]
+pub struct Proof {
+    pub public_inputs: Vec<Fp>,
+    pub bytes: Vec<u8>,
+}
+
+/// Proves `MyCircuit` statements with keys generated once up front.
+///
+/// `keygen_pk` costs far more than a single proof, so a `Prover` built once
+/// can be kept around and shared between threads: `prove` and `verify` only
+/// read the parameters and keys. Each proof gets its own transcript, since a
+/// transcript owns the buffer the proof ends up in.
+pub struct Prover<const WORD_BITS: u32 = 8> {
+    params: Params<EqAffine>,
+    pk: ProvingKey<EqAffine>,
+}
+
+impl<const WORD_BITS: u32> Prover<WORD_BITS> {
+    /// Generates the parameters and keys for `MyCircuit` at size `k`.
+    pub fn new(k: u32) -> Result<Self, Error> {
+        let params = Params::new(k);
+        let pk = keygen(&params, &MyCircuit::<Fp, WORD_BITS>::default())?;
+        Ok(Self { params, pk })
+    }
+
+    pub fn params(&self) -> &Params<EqAffine> {
+        &self.params
+    }
+
+    pub fn vk(&self) -> &VerifyingKey<EqAffine> {
+        self.pk.get_vk()
+    }
+
+    /// Proves `a & b` for the private operands `a` and `b`.
+    ///
+    /// `rng` supplies the blinding factors. Production callers pass `OsRng`;
+    /// a seeded RNG makes the proof bytes reproducible, which is only ever
+    /// wanted in tests.
+    pub fn prove(&self, a: u64, b: u64, mut rng: impl RngCore) -> Result<Proof, Error> {
+        let circuit = MyCircuit::<Fp, WORD_BITS> {
+            a: Some(Fp::from(a)),
+            b: Some(Fp::from(b)),
+            output_row: 0,
+        };
+        let public_inputs = vec![Fp::from(a & b)];
+        let bytes = prove(&self.params, &self.pk, circuit, &public_inputs, &mut rng)?;
+
+        Ok(Proof {
+            public_inputs,
+            bytes,
+        })
+    }
+
+    /// Checks `proof` against the public inputs it carries.
+    pub fn verify(&self, proof: &Proof) -> Result<(), Error> {
+        verify(&self.params, self.vk(), &proof.bytes, &proof.public_inputs)
+    }
+
+    /// `verify`, behind `verify_checked`'s range check on the public inputs.
+    pub fn verify_checked(&self, proof: &Proof) -> Result<(), AndError> {
+        verify_checked::<WORD_BITS>(self.params(), self.vk(), &proof.bytes, &proof.public_inputs)
+    }
+}
+
+/// Generates the proving key, and with it the verifying key, for `circuit`.
+///
+/// The keys only depend on the circuit's shape, so they are generated from
+/// `circuit.without_witnesses()` and can be reused for any witness.
+pub fn keygen<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    circuit: &C,
+) -> Result<ProvingKey<EqAffine>, Error> {
+    let blank = circuit.without_witnesses();
+    let vk = keygen_vk(params, &blank)?;
+    keygen_pk(params, vk, &blank)
+}
+
+/// Proves a single one-instance-column circuit under `pk`, returning the
+/// proof bytes `verify` checks. `rng` supplies the blinding factors.
+pub fn prove<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: C,
+    public_inputs: &[Fp],
+    rng: impl RngCore,
+) -> Result<Vec<u8>, Error> {
+    let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+    create_proof(
+        params,
+        pk,
+        &[circuit],
+        &[&[public_inputs]],
+        rng,
+        &mut transcript,
+    )?;
+    Ok(transcript.finalize())
+}
+
+/// Checks a single proof of a one-instance-column circuit against `vk`.
+pub fn verify(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    public_inputs: &[Fp],
+) -> Result<(), Error> {
+    let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(proof);
+    verify_proof(
+        params,
+        vk,
+        SingleVerifier::new(params),
+        &[&[public_inputs]],
+        &mut transcript,
+    )
+}
+
+/// `verify` for circuits whose public inputs are all `WORD_BITS`-bit words,
+/// such as `MyCircuit`'s `a & b`.
+///
+/// A public input outside `0..2^WORD_BITS` can never be the output of an
+/// instruction, so it is rejected with `AndError::OutOfRange` before any of
+/// the cryptographic work. This is only a cheap early out: the proof itself
+/// would fail to verify against such an input anyway.
+pub fn verify_checked<const WORD_BITS: u32>(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    public_inputs: &[Fp],
+) -> Result<(), AndError> {
+    if let Some(value) = public_inputs
+        .iter()
+        .find(|value| word_value::<Fp, WORD_BITS>(value).is_none())
+    {
+        return Err(AndError::out_of_range(value, WORD_BITS));
+    }
+    verify(params, vk, proof, public_inputs).map_err(AndError::Verification)
+}
+
+/// Verifies every proof against its own public inputs, all under `vk`, and
+/// returns one result per proof, in order, so that a bad proof doesn't hide
+/// how the others fared.
+///
+/// The proofs are independent: each gets its own transcript and
+/// `SingleVerifier`. With the `rayon` feature they are checked in parallel on
+/// rayon's global thread pool, otherwise one after the other on the calling
+/// thread.
+pub fn verify_batch(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proofs_and_inputs: &[(&[u8], &[Fp])],
+) -> Vec<Result<(), Error>> {
+    let verify_one =
+        |(proof, public_inputs): &(&[u8], &[Fp])| verify(params, vk, proof, public_inputs);
+
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        proofs_and_inputs.par_iter().map(verify_one).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        proofs_and_inputs.iter().map(verify_one).collect()
+    }
+}
+
+#[test]
+fn prover_reuse_test() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Prover<8>>();
+
+    let prover = Prover::<8>::new(min_k::<8>()).unwrap();
+    let proofs: Vec<_> = (0..100u64)
+        .map(|i| {
+            let (a, b) = ((i * 37) % 256, (i * 91 + 5) % 256);
+            prover.prove(a, b, OsRng).unwrap()
+        })
+        .collect();
+
+    for proof in &proofs {
+        prover.verify(proof).unwrap();
+    }
+
+    // A proof doesn't carry over to another result.
+    let mut forged = proofs[0].clone();
+    forged.public_inputs[0] += Fp::one();
+    assert!(prover.verify(&forged).is_err());
+}
+
+#[test]
+fn prove_verify_round_trip_test() {
+    let circuit = MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(3)),
+        b: Some(Fp::from(4)),
+        output_row: 0,
+    };
+    let params: Params<EqAffine> = Params::new(circuit.required_k());
+    let pk = keygen(&params, &circuit).unwrap();
+
+    let public_inputs = [Fp::from(3 & 4)];
+    assert_eq!(public_inputs[0], Fp::zero());
+    let proof = prove(&params, &pk, circuit, &public_inputs, OsRng).unwrap();
+    verify(&params, pk.get_vk(), &proof, &public_inputs).unwrap();
+
+    assert!(verify(&params, pk.get_vk(), &proof, &[Fp::one()]).is_err());
+    let mut tampered = proof.clone();
+    tampered[0] ^= 1;
+    assert!(verify(&params, pk.get_vk(), &tampered, &public_inputs).is_err());
+}
+
+#[test]
+fn verify_checked_test() {
+    let prover = Prover::<8>::new(min_k::<8>()).unwrap();
+    let proof = prover.prove(3, 4, OsRng).unwrap();
+    prover.verify_checked(&proof).unwrap();
+
+    let mut wrong = proof.clone();
+    wrong.public_inputs[0] = Fp::from(1);
+    assert!(matches!(
+        prover.verify_checked(&wrong),
+        Err(AndError::Verification(_))
+    ));
+
+    // Rejected on the input alone: there is no proof to check at all.
+    let out_of_range = Proof {
+        public_inputs: vec![Fp::from(300)],
+        bytes: vec![],
+    };
+    assert!(matches!(
+        prover.verify_checked(&out_of_range),
+        Err(AndError::OutOfRange { value, word_bits: 8 }) if value == Fp::from(300).to_repr()
+    ));
+}
+
+#[test]
+fn verify_batch_test() {
+    let prover = Prover::<8>::new(min_k::<8>()).unwrap();
+    let proofs: Vec<_> = [(3, 4), (0xF0, 0x3C), (0xFF, 0x81)]
+        .iter()
+        .map(|(a, b)| prover.prove(*a, *b, OsRng).unwrap())
+        .collect();
+    let wrong_inputs = [Fp::from(1)];
+
+    let batch = [
+        (&proofs[0].bytes[..], &proofs[0].public_inputs[..]),
+        (&proofs[1].bytes[..], &proofs[1].public_inputs[..]),
+        (&proofs[1].bytes[..], &wrong_inputs[..]),
+        (&proofs[2].bytes[..], &proofs[2].public_inputs[..]),
+    ];
+    let passed: Vec<_> = verify_batch(prover.params(), prover.vk(), &batch)
+        .iter()
+        .map(Result::is_ok)
+        .collect();
+    assert_eq!(passed, [true, true, false, true]);
+}
+
+#[test]
+fn seeded_prove_test() {
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    let prover = Prover::<8>::new(min_k::<8>()).unwrap();
+    let prove = |seed| {
+        prover
+            .prove(3, 4, ChaCha20Rng::seed_from_u64(seed))
+            .unwrap()
+    };
+
+    let proof = prove(1);
+    assert_eq!(proof.bytes, prove(1).bytes);
+
+    let other = prove(2);
+    assert_ne!(proof.bytes, other.bytes);
+    prover.verify(&proof).unwrap();
+    prover.verify(&other).unwrap();
+}
+
+/// Generates keys from `blank`, a circuit with no witnesses as keygen sees
+/// it, then proves and verifies `circuit` with them. The verifying key has
+/// to come out the same as one generated from `circuit` itself: keys only
+/// depend on the circuit's shape.
+#[cfg(test)]
+fn keygen_without_witnesses_case<C: Circuit<Fp>>(
+    k: u32,
+    blank: C,
+    circuit: C,
+    public_inputs: &[Fp],
+) {
+    let params: Params<EqAffine> = Params::new(k);
+    let vk = keygen_vk(&params, &blank).expect("keygen_vk without witnesses");
+    let pk = keygen_pk(&params, vk, &blank).expect("keygen_pk without witnesses");
+    assert_eq!(
+        vk_fingerprint(pk.get_vk()),
+        vk_fingerprint(&keygen_vk(&params, &circuit).unwrap())
+    );
+
+    let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+    create_proof(
+        &params,
+        &pk,
+        &[circuit],
+        &[&[public_inputs]],
+        OsRng,
+        &mut transcript,
+    )
+    .unwrap();
+    let proof = transcript.finalize();
+    verify(&params, pk.get_vk(), &proof, public_inputs).unwrap();
+}
+
+#[test]
+fn keygen_without_witnesses_test() {
+    // Every witness `None`, as `without_witnesses` leaves them. Any gate
+    // that needs a value to be assigned shows up here as a keygen error.
+    let circuit = || MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(0xA5)),
+        b: Some(Fp::from(0x3C)),
+        output_row: 0,
+    };
+    let public_inputs = [Fp::from(0xA5 & 0x3C)];
+    keygen_without_witnesses_case(
+        min_k::<8>(),
+        MyCircuit::default(),
+        circuit(),
+        &public_inputs,
+    );
+    keygen_without_witnesses_case(
+        min_k::<8>(),
+        circuit().without_witnesses(),
+        circuit(),
+        &public_inputs,
+    );
+    keygen_without_witnesses_case(
+        9,
+        LookupAndCircuit::default(),
+        LookupAndCircuit(circuit()),
+        &public_inputs,
+    );
+
+    let (a, blind, b) = (Fp::from(3), Fp::from(0x1234_5678), Fp::from(4));
+    keygen_without_witnesses_case(
+        7,
+        CommittedAndCircuit::<8>::default(),
+        CommittedAndCircuit {
+            a: Some(a),
+            blind: Some(blind),
+            b: Some(b),
+        },
+        &[Fp::from(3 & 4), commit_operand(a, blind)],
+    );
+}
+
+/// Estimates the size in bytes of a `MyCircuit` proof at size `k`, without
+/// generating keys or proving, from halo2's `CircuitCost` model.
+///
+/// The size is dominated by curve points and field elements of 32 bytes
+/// each. Every advice column adds a commitment and an evaluation per
+/// rotation it is queried at, and every lookup argument adds three
+/// commitments and five evaluations, so an instruction that brings its own
+/// column or lookup grows every proof, while one built from the existing
+/// gates only grows `k`. `k` itself enters only through the
+/// multiopening argument, logarithmically.
+pub fn proof_size_bytes<const WORD_BITS: u32>(k: u32) -> usize {
+    // The cost model lays the circuit out, so it wants witnesses.
+    let circuit = MyCircuit::<Fp, WORD_BITS> {
+        a: Some(Fp::zero()),
+        b: Some(Fp::zero()),
+        output_row: 0,
+    };
+    let cost = CircuitCost::<pasta_curves::Eq, _>::measure(k as usize, &circuit);
+    cost.proof_size(1).into()
+}
+
+/// Pins what `MyCircuit` costs, so that a change inflating it fails here
+/// rather than showing up as slower, bigger proofs. When a change is meant
+/// to move one of these, update the constant with it and say why in the
+/// commit.
+///
+/// `CircuitCost` keeps its counts to itself at this halo2 version, so they
+/// are read off the `ConstraintSystem` it would measure. The proof size it
+/// estimates is covered by `proof_size_bytes_test`.
+#[test]
+fn cost_regression_test() {
+    // Every gate reads `lhs` and `rhs` side by side and writes below `lhs`;
+    // a third column would widen every row of the proof.
+    const ADVICE_COLUMNS: usize = 2;
+    // Each lane of `decompose` is looked up in `even_bits`. Every lookup
+    // adds three commitments and a grand product to the proof.
+    const LOOKUPS: usize = 2;
+    // The lookups, at 2 + 2 for a selector times an advice cell + 1 for the
+    // table column. The degree-3 gates (`bits`, `bool`, `add carry` and
+    // `is equal`) sit below, so a gate of degree 6 or more would raise it and
+    // grow the extended domain.
+    const MAX_DEGREE: usize = 5;
+
+    let mut meta = ConstraintSystem::<Fp>::default();
+    MyCircuit::<Fp, 8>::configure(&mut meta);
+
+    assert_eq!(meta.num_advice_columns(), ADVICE_COLUMNS, "advice columns");
+    assert_eq!(meta.lookups().len(), LOOKUPS, "lookups");
+    assert_eq!(meta.degree(), MAX_DEGREE, "max degree");
+}
+
+#[test]
+fn proof_size_bytes_test() {
+    let k = 5;
+    let estimate = proof_size_bytes::<8>(k);
+
+    let prover = Prover::<8>::new(k).unwrap();
+    let actual = prover.prove(3, 4, OsRng).unwrap().bytes.len();
+
+    let tolerance = actual / 20;
+    assert!(
+        estimate + tolerance >= actual && estimate <= actual + tolerance,
+        "estimated {} bytes, the proof has {}",
+        estimate,
+        actual
+    );
+}
+
+/// Compares proving through one `Prover` against generating keys for every
+/// proof, as `gen_proof_and_verify` does. Run with
+/// `cargo test --release prover_reuse_bench -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn prover_reuse_bench() {
+    use std::time::Instant;
+
+    const PROOFS: u64 = 20;
+    let k = min_k::<8>();
+
+    let start = Instant::now();
+    for i in 0..PROOFS {
+        let prover = Prover::<8>::new(k).unwrap();
+        prover.prove(i, 0xFF, OsRng).unwrap();
+    }
+    let keygen_each = start.elapsed();
+
+    let start = Instant::now();
+    let prover = Prover::<8>::new(k).unwrap();
+    for i in 0..PROOFS {
+        prover.prove(i, 0xFF, OsRng).unwrap();
+    }
+    let reused = start.elapsed();
+
+    println!(
+        "{} proofs: {:?} with keygen per proof, {:?} with one Prover",
+        PROOFS, keygen_each, reused
+    );
+}
+
+/// The mean and the 99th percentile, by nearest rank, of `samples`.
+#[cfg(test)]
+fn latency_summary(
+    mut samples: Vec<std::time::Duration>,
+) -> (std::time::Duration, std::time::Duration) {
+    assert!(!samples.is_empty(), "no samples to summarize");
+    samples.sort();
+
+    let mean = samples.iter().sum::<std::time::Duration>() / samples.len() as u32;
+    let rank = (samples.len() * 99 + 99) / 100;
+    (mean, samples[rank - 1])
+}
+
+#[test]
+fn latency_summary_test() {
+    use std::time::Duration;
+
+    let samples: Vec<_> = (1..=100).rev().map(Duration::from_millis).collect();
+    assert_eq!(
+        latency_summary(samples),
+        (Duration::from_micros(50_500), Duration::from_millis(99))
+    );
+    let samples = vec![Duration::from_millis(3); 5];
+    assert_eq!(
+        latency_summary(samples),
+        (Duration::from_millis(3), Duration::from_millis(3))
+    );
+}
+
+/// Times verification alone, against keys and proofs made up front, so none
+/// of keygen or proving leaks into the numbers; `prover_reuse_bench` covers
+/// those. Reports the mean and p99 of `verify` at `k = 5` and `k = 12`, and
+/// of `verify_batch` over 16 proofs, with the per-proof mean next to it.
+///
+/// Verification is dominated by the multiscalar multiplication checking the
+/// inner product argument, which is linear in `2^k` rather than in the
+/// rows actually used, so it is `k` that sets the cost. `verify_batch`
+/// checks every proof on its own, and any amortization comes from running
+/// them in parallel with the `rayon` feature. Run with
+/// `cargo test --release verify_latency_bench -- --ignored --nocapture`,
+/// adding `--features rayon` for the parallel batch.
+#[test]
+#[ignore]
+fn verify_latency_bench() {
+    use std::time::Instant;
+
+    const SAMPLES: usize = 200;
+    const BATCH: usize = 16;
+    const BATCHES: usize = 20;
+
+    for k in [5, 12] {
+        let prover = Prover::<8>::new(k).unwrap();
+        let proofs: Vec<_> = (0..BATCH as u64)
+            .map(|i| prover.prove(i * 37 % 256, 0xA5, OsRng).unwrap())
+            .collect();
+
+        let single = (0..SAMPLES)
+            .map(|i| {
+                let start = Instant::now();
+                prover.verify(&proofs[i % BATCH]).unwrap();
+                start.elapsed()
+            })
+            .collect();
+        let (mean, p99) = latency_summary(single);
+        println!("k = {}: verify mean {:?}, p99 {:?}", k, mean, p99);
+
+        let batch: Vec<_> = proofs
+            .iter()
+            .map(|proof| (&proof.bytes[..], &proof.public_inputs[..]))
+            .collect();
+        let batched = (0..BATCHES)
+            .map(|_| {
+                let start = Instant::now();
+                let results = verify_batch(prover.params(), prover.vk(), &batch);
+                let elapsed = start.elapsed();
+                assert!(results.iter().all(Result::is_ok));
+                elapsed
+            })
+            .collect();
+        let (mean, p99) = latency_summary(batched);
+        println!(
+            "k = {}: verify_batch of {} mean {:?}, p99 {:?}, {:?} per proof",
+            k,
+            BATCH,
+            mean,
+            p99,
+            mean / BATCH as u32
+        );
+    }
+}
+
+/// `MyCircuit` with its AND done by `naive_and`. The chip's table is still
+/// allocated, as the circuit shares `MyCircuit`'s configuration.
+#[cfg(test)]
+#[derive(Default)]
+struct NaiveAndCircuit<const WORD_BITS: u32>(MyCircuit<Fp, WORD_BITS>);
+
+#[cfg(test)]
+impl<const WORD_BITS: u32> Circuit<Fp> for NaiveAndCircuit<WORD_BITS> {
+    type Config = AndConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        MyCircuit::<Fp, WORD_BITS>::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let field_chip = AndChip::<Fp, WORD_BITS>::construct(config);
+        field_chip.alloc_table(&mut layouter.namespace(|| "alloc table"))?;
+
+        let a = field_chip.load_private(layouter.namespace(|| "load a"), self.0.a)?;
+        let b = field_chip.load_private(layouter.namespace(|| "load b"), self.0.b)?;
+        let a_and_b = field_chip.naive_and(layouter.namespace(|| "a & b"), a, b)?;
+
+        field_chip.expose_public(layouter.namespace(|| "expose a_and_b"), a_and_b, 0, 0)
+    }
+}
+
+#[test]
+fn naive_and_test() {
+    for a in (0..=u8::MAX).step_by(15) {
+        for b in (0..=u8::MAX).step_by(17) {
+            let (a, b) = (a as u64, b as u64);
+            for instruction in [Instruction::And, Instruction::NaiveAnd] {
+                assert_eq!(
+                    instruction_test::<8>(instruction, &[a, b], &[a & b]),
+                    Ok(()),
+                    "{:?}({:#04x}, {:#04x})",
+                    instruction,
+                    a,
+                    b
+                );
+            }
+        }
+    }
+    assert!(instruction_test::<8>(Instruction::NaiveAnd, &[0xF0, 0x3C], &[0x3C]).is_err());
+    assert!(instruction_test::<8>(Instruction::NaiveAnd, &[0x100, 0xFF], &[0]).is_err());
+
+    let circuit = |a: u64, b: u64| MyCircuit::<Fp, 16> {
+        a: Some(Fp::from(a)),
+        b: Some(Fp::from(b)),
+        output_row: 0,
+    };
+    for (a, b) in [(0xA5A5, 0x0FF0), (0xFFFF, 0x1234)] {
+        let expected = vec![vec![Fp::from(a & b)]];
+        let prover = MockProver::run(9, &circuit(a, b), expected.clone()).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+        let prover = MockProver::run(9, &NaiveAndCircuit(circuit(a, b)), expected).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
+
+/// Proves `circuit` once with the real prover, and returns the advice rows
+/// it lays out, the time `create_proof` took and the proof's size in bytes.
+#[cfg(test)]
+fn prove_once<C: Circuit<Fp>>(
+    k: u32,
+    circuit: C,
+    public_inputs: &[Fp],
+) -> (usize, std::time::Duration, usize) {
+    let rows = SynthesisTrace::of(&circuit).unwrap().advice_rows;
+
+    let params = Params::new(k);
+    let pk = keygen(&params, &circuit).unwrap();
+
+    let start = std::time::Instant::now();
+    let proof = prove(&params, &pk, circuit, public_inputs, OsRng).unwrap();
+    let elapsed = start.elapsed();
+
+    verify(&params, pk.get_vk(), &proof, public_inputs).unwrap();
+    (rows, elapsed, proof.len())
+}
+
+/// Compares `and` with `naive_and` at 8 and 16 bits, on the same operands
+/// and at the same `k`. Run with
+/// `cargo test --release and_approaches_bench -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn and_approaches_bench() {
+    fn compare<const WORD_BITS: u32>(k: u32, a: u64, b: u64) {
+        let circuit = || MyCircuit::<Fp, WORD_BITS> {
+            a: Some(Fp::from(a)),
+            b: Some(Fp::from(b)),
+            output_row: 0,
+        };
+        let public_inputs = [Fp::from(a & b)];
+
+        for (name, (rows, time, size)) in [
+            ("spread", prove_once(k, circuit(), &public_inputs)),
+            (
+                "naive",
+                prove_once(k, NaiveAndCircuit(circuit()), &public_inputs),
+            ),
+        ] {
+            println!(
+                "{}-bit {}: {} rows, proved in {:?}, {} byte proof",
+                WORD_BITS, name, rows, time, size
+            );
+        }
+    }
+
+    // The naive AND outgrows `min_k::<8>()`; at 16 bits the table sets `k`.
+    compare::<8>(6, 0xA5, 0x3C);
+    compare::<16>(9, 0xA5A5, 0x3C3C);
+}
+
+#[cfg(feature = "layout-viz")]
+#[test]
+fn circuit_layout_test() {
+    const WORD_BITS: u32 = 8;
+    let k = 5;
+
+    // Prepare the private and public inputs to the circuit!
+    const A: u64 = 7;
+    const B: u64 = 6;
+    let a = Fp::from(A);
+    let b = Fp::from(B);
+
+    // Instantiate the circuit with the private inputs.
+    let circuit = MyCircuit::<Fp, WORD_BITS> {
+        a: Some(a),
+        b: Some(b),
+        output_row: 0,
+    };
+    use plotters::prelude::*;
+    let root = BitMapBackend::new("layout.png", (1920, 1080)).into_drawing_area();
+    root.fill(&WHITE).unwrap();
+    let root = root
+        .titled("Bitwise AND Circuit Layout", ("sans-serif", 60))
+        .unwrap();
+
+    halo2_proofs::dev::CircuitLayout::default()
+        .mark_equality_cells(true)
+        .show_equality_constraints(true)
+        // The first argument is the size parameter for the circuit.
+        .render(k, &circuit, &root)
+        .unwrap();
+
+    let dot_string = halo2_proofs::dev::circuit_dot_graph(&circuit);
+    let mut dot_graph = std::fs::File::create("circuit.dot").unwrap();
+    std::io::Write::write_all(&mut dot_graph, dot_string.as_bytes()).unwrap();
+
+    root.present().unwrap();
+    assert!(std::fs::metadata("layout.png").unwrap().len() > 0);
+}
+
+/// Checks the region graph of the `7 & 6` circuit against the golden DOT file
+/// in `snapshots/`, which records how `synthesize` nests its regions and
+/// namespaces.
+///
+/// Run with `UPDATE_SNAPSHOTS=1` to rewrite the golden file after an
+/// intended change, and review the diff before committing it. Without it, a
+/// missing golden file fails the test rather than being written.
+///
+/// `circuit_dot_graph` is behind halo2's `dev-graph` feature, so this only
+/// runs under `cargo test --features layout-viz`.
+#[cfg(feature = "layout-viz")]
+#[test]
+fn circuit_dot_snapshot_test() {
+    let circuit = MyCircuit::<Fp, 8> {
+        a: Some(Fp::from(7)),
+        b: Some(Fp::from(6)),
+        output_row: 0,
+    };
+
+    // Line endings and indentation don't change the graph.
+    let normalize = |dot: &str| {
+        dot.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| format!("{}\n", line))
+            .collect::<String>()
+    };
+    let dot = normalize(&halo2_proofs::dev::circuit_dot_graph(&circuit));
+
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("snapshots/circuit.dot");
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &dot).unwrap();
+        return;
+    }
+
+    let golden = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "can't read {} ({}); rerun with UPDATE_SNAPSHOTS=1 to write it",
+            path.display(),
+            e
+        )
+    });
+    let golden = normalize(&golden);
+    assert!(
+        dot == golden,
+        "the circuit's DOT graph no longer matches {}; rerun with UPDATE_SNAPSHOTS=1 if \
+         the change is intended\n--- golden\n{}\n--- actual\n{}",
+        path.display(),
+        golden,
+        dot
+    );
+}
@@ -0,0 +1,32 @@
+use assert_cmd::Command;
+
+#[test]
+fn cli_proves_and_verifies() {
+    let assert = Command::cargo_bin("and-circuit-example")
+        .unwrap()
+        .args(["--a", "3", "--b", "4"])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("3 & 4 = 0"), "{}", stdout);
+    assert!(stdout.contains("proof: "), "{}", stdout);
+    assert!(stdout.lines().any(|line| line == "verified"), "{}", stdout);
+}
+
+#[test]
+fn cli_rejects_out_of_range_operands() {
+    Command::cargo_bin("and-circuit-example")
+        .unwrap()
+        .args(["--a", "256", "--b", "4"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn cli_runs_the_demo_without_args() {
+    Command::cargo_bin("and-circuit-example")
+        .unwrap()
+        .assert()
+        .success();
+}